@@ -7,12 +7,79 @@ import shutil
 
 OUT_PATH = "./out"
 
-def call(input_types: dict, output_types: dict, sig: str, *args: str) -> None:
+def abi_type(param: dict) -> str:
+    """
+    Canonical type of an ABI parameter, spelling out tuples as their components (ex: `(uint256,address)[]`)
+
+    @param param: (dict) an input or output entry of the contract ABI
+    """
+    ty = param["type"]
+    if ty.startswith("tuple"):
+        components = ",".join(abi_type(c) for c in param["components"])
+        return f"({components}){ty[len('tuple'):]}"
+    return ty
+
+next_id = 0
+
+def send(cmd: dict) -> dict:
+    """
+    Sends one command to the EVM, tagged with a fresh id, and returns its JSON response
+
+    @param cmd: (dict) the command, as described in the README
+    """
+    global next_id
+    next_id += 1
+    cmd["id"] = next_id
+    proc.stdin.write(json.dumps(cmd) + "\n")
+    proc.stdin.flush()
+    return json.loads(proc.stdout.readline())
+
+def show(response: dict) -> None:
+    """
+    Prints a response the way a person wants to read it: the decoded result, revert reason or error, then any events
+
+    @param response: (dict) a response from the EVM
+    """
+    status = response["status"]
+    if status == "error":
+        print(f"error: {response['error']}")
+    elif status == "reverted":
+        print(response["reason"])
+    elif status == "failed":
+        print(f"failed: {response['error']}")
+    elif "codeSize" in response:
+        print(f"deployed {response['name']} at {response['address']} ({response['codeSize']} bytes of runtime code, {response['gasUsed']} gas)")
+    elif "outputs" in response:
+        outputs = response["outputs"]
+        if "error" in response:
+            print(f"error: {response['error']} ({response['returnData']})")
+        elif len(outputs) == 1:
+            print(outputs[0])
+        else:
+            print(tuple(outputs))
+    elif "output" in response:
+        print(response["output"])
+    elif "gas" in response:
+        print(response["gas"])
+    else:
+        fields = {k: v for k, v in response.items() if k not in ("id", "status")}
+        if not fields:
+            print("ok")
+        elif len(fields) == 1:
+            print(next(iter(fields.values())))
+        else:
+            print(json.dumps(fields))
+
+    for log in response.get("logs", []):
+        print(log["decoded"])
+
+def call(input_types: dict, output_types: dict, sig: str, *args: str, mode: str = None) -> None:
     """
     Writes commands to the EVM, kind of like foundry cast
     
     @param sig: (str) the function signature to call in the contract (ex: `setNumber(uint256)`)
     @param args: (str) arguments to pass into the function
+    @param mode: (str) `call` to simulate read-only or `send` to commit, otherwise picked from the ABI
     """
     if sig not in input_types or sig not in output_types:
         print(f"Function signature '{sig}' is incorrect.")
@@ -25,9 +92,158 @@ def call(input_types: dict, output_types: dict, sig: str, *args: str) -> None:
         "input_types": input_types[sig],
         "output_types": output_types[sig]
     }
-    proc.stdin.write(json.dumps(cmd) + "\n")
-    proc.stdin.flush()
-    print(proc.stdout.readline())
+    if mode is not None:
+        cmd["mode"] = mode
+    show(send(cmd))
+
+def prefixed_call(input_types: dict, output_types: dict, prefix: str, sig: str, *args: str) -> None:
+    """
+    Runs a command prefixed with `call` or `send` the way the binary's own REPL does: `call` is always read-only,
+    while `send` leaves the mode to the ABI, so view and pure functions are still only called
+
+    @param prefix: (str) `call` or `send`
+    @param sig: (str) the function signature to call in the contract (ex: `setNumber(uint256)`)
+    @param args: (str) arguments to pass into the function
+    """
+    call(input_types, output_types, sig, *args, mode="call" if prefix == "call" else None)
+
+def access_list(input_types: dict, sig: str, *args: str) -> None:
+    """
+    Prints the EIP-2930 access list a call would need, and the gas it uses with and without it
+
+    @param sig: (str) the function signature to call in the contract (ex: `setNumber(uint256)`)
+    @param args: (str) arguments to pass into the function
+    """
+    if sig not in input_types:
+        print(f"Function signature '{sig}' is incorrect.")
+        return
+
+    cmd = {
+        "type": "access_list",
+        "signature": sig,
+        "args": list(args),
+        "input_types": input_types[sig]
+    }
+    show(send(cmd))
+
+def estimate(input_types: dict, sig: str, *args: str) -> None:
+    """
+    Prints the lowest gas limit a call succeeds with, without changing the contract's storage
+
+    @param sig: (str) the function signature to call in the contract (ex: `setNumber(uint256)`)
+    @param args: (str) arguments to pass into the function
+    """
+    if sig not in input_types:
+        print(f"Function signature '{sig}' is incorrect.")
+        return
+
+    cmd = {
+        "type": "estimate_gas",
+        "signature": sig,
+        "args": list(args),
+        "input_types": input_types[sig]
+    }
+    show(send(cmd))
+
+def deploy(constructor_types: list, *args: str, value: str = None) -> None:
+    """
+    Redeploys the contract, ABI-encoding the constructor arguments onto its initcode
+
+    @param constructor_types: (list) the constructor's input types from the ABI
+    @param args: (str) arguments to pass into the constructor
+    @param value: (str) wei to send along with the deployment, for payable constructors
+    """
+    cmd = {
+        "type": "deploy",
+        "args": list(args),
+        "input_types": constructor_types
+    }
+    if value is not None:
+        cmd["value"] = value
+    show(send(cmd))
+
+def deploy_contract(combined: dict, contract: str, *args: str) -> None:
+    """
+    Deploys another contract from the compiled file at a new address and makes it the active one
+
+    @param combined: (dict) solc's combined JSON output, for the contract's constructor types
+    @param contract: (str) name of the contract to deploy
+    @param args: (str) arguments to pass into its constructor
+    """
+    path = f"{OUT_PATH}/{contract}.bin"
+    if not os.path.exists(path):
+        print(f"Contract {contract} not found in output")
+        return
+    with open(path, 'r') as f:
+        bytecode = f.read().strip()
+
+    constructor_types = []
+    for key, compiled in combined["contracts"].items():
+        if key.split(":")[-1] == contract:
+            for method in compiled["abi"]:
+                if method["type"] == "constructor":
+                    constructor_types = [abi_type(inp) for inp in method["inputs"]]
+
+    show(send({"type": "deploy", "contract": contract, "bytecode": bytecode, "args": list(args), "input_types": constructor_types}))
+
+# commands that read or write state directly: the command, its JSON type and the fields its arguments fill in,
+# followed by an optional address
+STATE_COMMANDS = {
+    "storage": ("get_storage", ["slot"]),
+    "set-storage": ("set_storage", ["slot", "value"]),
+    "balance": ("get_balance", []),
+    "set-balance": ("set_balance", ["value"]),
+    "nonce": ("get_nonce", []),
+    "set-nonce": ("set_nonce", ["value"]),
+}
+
+def state(command: str, *args: str) -> None:
+    """
+    Reads or writes a storage slot, balance or nonce, of the active contract or of the address given last
+
+    @param command: (str) one of the STATE_COMMANDS
+    @param args: (str) the command's arguments, optionally followed by an address
+    """
+    ty, fields = STATE_COMMANDS[command]
+    if len(args) not in (len(fields), len(fields) + 1):
+        print(f"usage: {command} {' '.join(f'<{field}>' for field in fields)} [address]")
+        return
+
+    cmd = {"type": ty, **dict(zip(fields, args))}
+    if len(args) > len(fields):
+        cmd["address"] = args[-1]
+    show(send(cmd))
+
+def debug(input_types: dict, sig: str, *args: str) -> None:
+    """
+    Steps through a call in the EVM's interactive debugger until the user quits
+
+    @param sig: (str) the function signature to debug (ex: `setNumber(uint256)`)
+    @param args: (str) arguments to pass into the function
+    """
+    if sig not in input_types:
+        print(f"Function signature '{sig}' is incorrect.")
+        return
+
+    cmd = {
+        "type": "debug",
+        "signature": sig,
+        "args": list(args),
+        "input_types": input_types[sig]
+    }
+    response = send(cmd)
+    show(response)
+    if response["status"] == "error":
+        return
+
+    while True:
+        line = input("dbg> ").strip()
+        proc.stdin.write(line + "\n") # debugger commands are plain lines, answered with the same id
+        proc.stdin.flush()
+        show(json.loads(proc.stdout.readline()))
+
+        if line in ("quit", "q"):
+            break
 
 if __name__ == '__main__':
     parser = argparse.ArgumentParser(description="A script to compile a Solidity contract and run it on the mini EVM")
@@ -35,6 +251,12 @@ if __name__ == '__main__':
     parser.add_argument("--file", "-f", type=str, help="Solidity file to compile")
     parser.add_argument("--contract", "-c", type=str, default=None, help="Specific contract in a file to compile.")
     parser.add_argument("--target-folder", "-t", default='test_files', type=str, help="Folder to look for the file")
+    parser.add_argument("--trace", action="store_true", help="Print an EIP-3155 JSON trace of every call to stderr")
+    parser.add_argument("--tracer", choices=["eip3155", "callTracer", "prestateTracer"], default=None, help="Tracer whose JSON output is printed to stderr for every call")
+    parser.add_argument("--gas-report", choices=["table", "json"], default=None, help="Print gas used per function across the session to stderr on exit")
+    parser.add_argument("--profile", type=str, default=None, help="Write folded stacks of the gas used per opcode to this file and print the hottest instructions on exit")
+    parser.add_argument("--chrome-trace", type=str, default=None, help="Write the session as a Chrome/Perfetto trace-event JSON file on exit")
+    parser.add_argument("--gas", action="store_true", help="Print the intrinsic and execution gas of every transaction to stderr")
 
     args = parser.parse_args()
 
@@ -95,8 +317,35 @@ if __name__ == '__main__':
         check=True
     )
 
+    evm_args = ["./target/release/mini-evm", binary]
+    if args.trace:
+        evm_args.append("--trace")
+    if args.tracer is not None:
+        evm_args.extend(["--tracer", args.tracer])
+    if args.gas:
+        evm_args.append("--gas")
+    if args.chrome_trace is not None:
+        evm_args.extend(["--chrome-trace", args.chrome_trace])
+    if args.profile is not None:
+        evm_args.extend(["--profile", args.profile])
+    if args.gas_report is not None:
+        evm_args.extend(["--gas-report", args.gas_report])
+    name = contract if contract is not None else bin_files[0].removesuffix(".bin")
+    evm_args.extend(["--name", name])
+
+    # the selected contract's ABI lets the EVM decode custom errors
+    with open(json_path, 'r') as f:
+        combined = json.load(f)
+    for key, compiled in combined["contracts"].items():
+        if key.split(":")[-1] == name:
+            abi_path = f"{OUT_PATH}/{name}.abi.json"
+            with open(abi_path, 'w') as f:
+                json.dump(compiled["abi"], f)
+            evm_args.extend(["--abi", abi_path])
+            break
+
     proc = subprocess.Popen(
-        ["./target/release/mini-evm", binary],
+        evm_args,
         stdin=subprocess.PIPE,
         stdout=subprocess.PIPE,
         text=True,
@@ -108,16 +357,20 @@ if __name__ == '__main__':
 
     input_types = {}
     output_types = {}
+    constructor_types = []
+    selected = name
     contracts = abi["contracts"]
     for contract, _ in contracts.items():
         for method in contracts[contract]["abi"]:
+            if method["type"] == "constructor" and contract.split(":")[-1] == selected:
+                constructor_types = [abi_type(inp) for inp in method["inputs"]]
             if method["type"] == "function":
                 name = method["name"]
-                types = [inp["type"] for inp in method["inputs"]]
+                types = [abi_type(inp) for inp in method["inputs"]]
                 sig = f"{name}({','.join(types)})"
                 input_types[sig] = types
                 if "outputs" in method:
-                    output_types[sig] = [t["type"] for t in method["outputs"]]
+                    output_types[sig] = [abi_type(t) for t in method["outputs"]]
 
     try:
         while True:
@@ -125,10 +378,38 @@ if __name__ == '__main__':
             if txn in ("exit", "quit"):
                 proc.stdin.write(json.dumps({"type": "exit"}) + "\n")
                 proc.stdin.flush()
+                proc.wait(timeout=5) # let the gas report print before exiting
                 break
 
             parts = txn.split()
-            call(input_types, output_types, parts[0], *parts[1:])
+            if not parts:
+                continue
+            if parts[0] == "debug":
+                debug(input_types, parts[1], *parts[2:])
+            elif parts[0] == "access-list":
+                access_list(input_types, parts[1], *parts[2:])
+            elif parts[0] == "deploy":
+                # `deploy --value <wei> <args>` sends ether to a payable constructor
+                if len(parts) > 2 and parts[1] == "--value":
+                    deploy(constructor_types, *parts[3:], value=parts[2])
+                else:
+                    deploy(constructor_types, *parts[1:])
+            elif parts[0] == "deploy-contract" and len(parts) > 1:
+                deploy_contract(abi, parts[1], *parts[2:])
+            elif parts[0] == "use" and len(parts) == 2:
+                show(send({"type": "use", "contract": parts[1]}))
+            elif parts[0] in STATE_COMMANDS:
+                state(parts[0], *parts[1:])
+            elif parts[0] == "snapshot":
+                show(send({"type": "snapshot"}))
+            elif parts[0] == "revert" and len(parts) == 2 and parts[1].isdigit():
+                show(send({"type": "revert", "snapshot": int(parts[1])}))
+            elif parts[0] in ("call", "send") and len(parts) > 1:
+                prefixed_call(input_types, output_types, parts[0], parts[1], *parts[2:])
+            elif parts[0] == "estimate":
+                estimate(input_types, parts[1], *parts[2:])
+            else:
+                call(input_types, output_types, parts[0], *parts[1:])
     except:
         pass # don't care about ctrl-c error
     finally:
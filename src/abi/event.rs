@@ -0,0 +1,68 @@
+use primitive_types::U256;
+use crate::Log;
+use crate::tracing::{format_address, format_word};
+use super::{AbiType, AbiValue, decode, keccak256};
+use super::json::{Abi, AbiEvent, AbiParam, format_call};
+
+// Matches a log against the events in the ABI and decodes its fields in declaration order.
+// Non-anonymous events are found by topic0; anonymous ones have no topic0 and are tried in turn.
+pub fn decode_log<'a>(log: &Log, abi: &'a Abi) -> Option<(&'a AbiEvent, Vec<AbiValue>)> {
+    if let Some(topic0) = log.topics.first() {
+        let mut buf: [u8; 32] = [0u8; 32];
+        topic0.to_big_endian(&mut buf);
+
+        let found: Option<(&AbiEvent, Vec<AbiValue>)> = abi.events.iter()
+            .filter(|event: &&AbiEvent| !event.anonymous && keccak256(event.signature().as_bytes()) == buf)
+            .find_map(|event: &AbiEvent| Some((event, decode_fields(event, &log.topics[1..], &log.data).ok()?)));
+        if found.is_some() {
+            return found;
+        }
+    }
+
+    abi.events.iter()
+        .filter(|event: &&AbiEvent| event.anonymous)
+        .find_map(|event: &AbiEvent| Some((event, decode_fields(event, &log.topics, &log.data).ok()?)))
+}
+
+// `Transfer(from=0x.., to=0x.., value=100)` for known events, the raw topics and data otherwise.
+pub fn format_log(log: &Log, abi: &Abi) -> String {
+    match decode_log(log, abi) {
+        Some((event, values)) => format_call(&event.name, &event.inputs, &values),
+        None => {
+            let topics: Vec<String> = log.topics.iter().map(|topic| format_word(*topic)).collect();
+            format!("Log(address={}, topics=[{}], data=0x{})", format_address(log.address), topics.join(", "), hex::encode(&log.data))
+        },
+    }
+}
+
+fn decode_fields(event: &AbiEvent, topics: &[U256], data: &[u8]) -> Result<Vec<AbiValue>, String> {
+    let indexed: Vec<&AbiParam> = event.inputs.iter().filter(|param: &&AbiParam| param.indexed).collect();
+    if indexed.len() != topics.len() {
+        return Err(format!("{} has {} indexed fields, the log has {} topics", event.name, indexed.len(), topics.len()));
+    }
+
+    let data_types: Vec<AbiType> = event.inputs.iter()
+        .filter(|param: &&AbiParam| !param.indexed)
+        .map(|param: &AbiParam| param.ty.clone())
+        .collect();
+    let mut data_values: std::vec::IntoIter<AbiValue> = decode(&data_types, data)?.into_iter();
+    let mut topic_values: std::slice::Iter<'_, U256> = topics.iter();
+
+    event.inputs.iter()
+        .map(|param: &AbiParam| {
+            if !param.indexed {
+                return data_values.next().ok_or("missing data field".to_string());
+            }
+
+            let topic: U256 = *topic_values.next().ok_or("missing topic".to_string())?;
+            let mut word: [u8; 32] = [0u8; 32];
+            topic.to_big_endian(&mut word);
+
+            // indexed dynamic values only leave their keccak256 hash in the topic
+            if param.ty.is_dynamic() || matches!(param.ty, AbiType::FixedArray(..) | AbiType::Tuple(_)) {
+                return Ok(AbiValue::FixedBytes(word.to_vec()));
+            }
+            decode(std::slice::from_ref(&param.ty), &word)?.pop().ok_or("missing topic value".to_string())
+        })
+        .collect()
+}
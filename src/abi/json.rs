@@ -0,0 +1,207 @@
+use serde_json::Value;
+use super::{AbiType, AbiValue, function_selector, split_top_level};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbiParam {
+    pub name: String,
+    pub ty: AbiType,
+    pub indexed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbiFunction {
+    pub name: String,
+    pub inputs: Vec<AbiParam>,
+    pub outputs: Vec<AbiParam>,
+    pub state_mutability: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbiError {
+    pub name: String,
+    pub inputs: Vec<AbiParam>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbiEvent {
+    pub name: String,
+    pub inputs: Vec<AbiParam>,
+    pub anonymous: bool,
+}
+
+// The parts of a contract's JSON ABI the REPL works with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Abi {
+    pub constructor: Option<AbiFunction>,
+    pub functions: Vec<AbiFunction>,
+    pub errors: Vec<AbiError>,
+    pub events: Vec<AbiEvent>,
+}
+
+impl Abi {
+    pub fn from_json(json: &Value) -> Result<Abi, String> {
+        let items: &Vec<Value> = json.as_array().ok_or("ABI must be a JSON array".to_string())?;
+        let mut abi: Abi = Abi::default();
+
+        for item in items {
+            let name: String = item["name"].as_str().unwrap_or_default().to_string();
+            match item["type"].as_str().unwrap_or("function") {
+                "function" => abi.functions.push(AbiFunction {
+                    name,
+                    inputs: parse_params(&item["inputs"])?,
+                    outputs: parse_params(&item["outputs"])?,
+                    state_mutability: item["stateMutability"].as_str().unwrap_or("nonpayable").to_string(),
+                }),
+                "constructor" => abi.constructor = Some(AbiFunction {
+                    name: "constructor".to_string(),
+                    inputs: parse_params(&item["inputs"])?,
+                    outputs: vec![],
+                    state_mutability: item["stateMutability"].as_str().unwrap_or("nonpayable").to_string(),
+                }),
+                "error" => abi.errors.push(AbiError { name, inputs: parse_params(&item["inputs"])? }),
+                "event" => abi.events.push(AbiEvent {
+                    name,
+                    inputs: parse_params(&item["inputs"])?,
+                    anonymous: item["anonymous"].as_bool().unwrap_or(false),
+                }),
+                // fallback and receive take no arguments the REPL could encode
+                _ => {},
+            }
+        }
+        Ok(abi)
+    }
+
+    pub fn function(&self, signature: &str) -> Option<&AbiFunction> {
+        self.functions.iter().find(|function: &&AbiFunction| function.signature() == signature)
+    }
+
+    pub fn error(&self, selector: [u8; 4]) -> Option<&AbiError> {
+        self.errors.iter().find(|error: &&AbiError| error.selector() == selector)
+    }
+}
+
+impl AbiFunction {
+    pub fn signature(&self) -> String {
+        signature(&self.name, &self.inputs)
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        function_selector(&self.signature())
+    }
+}
+
+impl AbiError {
+    pub fn signature(&self) -> String {
+        signature(&self.name, &self.inputs)
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        function_selector(&self.signature())
+    }
+}
+
+impl AbiEvent {
+    pub fn signature(&self) -> String {
+        signature(&self.name, &self.inputs)
+    }
+}
+
+pub fn signature(name: &str, params: &[AbiParam]) -> String {
+    let types: Vec<String> = params.iter().map(|param: &AbiParam| param.ty.to_string()).collect();
+    format!("{}({})", name, types.join(","))
+}
+
+// A function from its signature alone, as cast takes them: `balanceOf(address)`, optionally followed by
+// the output types as in `balanceOf(address)(uint256)`.
+pub fn parse_signature(text: &str) -> Result<AbiFunction, String> {
+    let invalid = || format!("invalid signature: {}", text);
+    let open: usize = text.find('(').ok_or_else(invalid)?;
+    let name: &str = &text[..open];
+    if name.is_empty() || !name.chars().all(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+        return Err(invalid());
+    }
+
+    let (inputs, rest) = split_param_list(&text[open..]).ok_or_else(invalid)?;
+    let outputs: Vec<&str> = match rest {
+        "" => vec![],
+        rest => match split_param_list(rest) {
+            Some((outputs, "")) => outputs,
+            _ => return Err(invalid()),
+        },
+    };
+
+    Ok(AbiFunction {
+        name: name.to_string(),
+        inputs: parse_types(inputs)?,
+        outputs: parse_types(outputs)?,
+        state_mutability: "nonpayable".to_string(),
+    })
+}
+
+// `(uint256,(bool,address))rest` into its top-level types and whatever follows the closing parenthesis
+fn split_param_list(text: &str) -> Option<(Vec<&str>, &str)> {
+    if !text.starts_with('(') {
+        return None;
+    }
+
+    let mut depth: usize = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((split_top_level(&text[1..i]).ok()?, &text[i + 1..]));
+                }
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
+fn parse_types(types: Vec<&str>) -> Result<Vec<AbiParam>, String> {
+    types.into_iter()
+        .map(|ty: &str| Ok(AbiParam { name: String::new(), ty: AbiType::parse(ty)?, indexed: false }))
+        .collect()
+}
+
+// `Name(a=1, b=2)`, falling back to positional values for parameters without a name.
+pub fn format_call(name: &str, params: &[AbiParam], values: &[AbiValue]) -> String {
+    let fields: Vec<String> = params.iter().zip(values.iter())
+        .map(|(param, value)| match param.name.as_str() {
+            "" => value.to_string(),
+            field => format!("{}={}", field, value),
+        })
+        .collect();
+    format!("{}({})", name, fields.join(", "))
+}
+
+fn parse_params(json: &Value) -> Result<Vec<AbiParam>, String> {
+    let Some(params) = json.as_array() else {
+        return Ok(vec![]);
+    };
+
+    params.iter()
+        .map(|param: &Value| Ok(AbiParam {
+            name: param["name"].as_str().unwrap_or_default().to_string(),
+            ty: AbiType::parse(&canonical_type(param)?)?,
+            indexed: param["indexed"].as_bool().unwrap_or(false),
+        }))
+        .collect()
+}
+
+// Tuples are given as `tuple` (or `tuple[]`) with their components listed separately.
+fn canonical_type(param: &Value) -> Result<String, String> {
+    let ty: &str = param["type"].as_str().ok_or(format!("ABI parameter without a type: {}", param))?;
+    let Some(suffix) = ty.strip_prefix("tuple") else {
+        return Ok(ty.to_string());
+    };
+
+    let components: Vec<String> = param["components"].as_array()
+        .ok_or(format!("tuple without components: {}", param))?
+        .iter()
+        .map(canonical_type)
+        .collect::<Result<Vec<String>, String>>()?;
+    Ok(format!("({}){}", components.join(","), suffix))
+}
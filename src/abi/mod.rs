@@ -0,0 +1,588 @@
+use std::fmt;
+use primitive_types::U256;
+use tiny_keccak::{Hasher, Keccak};
+
+pub mod event;
+pub mod json;
+pub mod revert;
+
+pub use json::Abi;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiType {
+    Uint(usize),
+    Int(usize),
+    Address,
+    Bool,
+    FixedBytes(usize),
+    Bytes,
+    String,
+    Array(Box<AbiType>),
+    FixedArray(Box<AbiType>, usize),
+    Tuple(Vec<AbiType>),
+}
+
+// Int values are kept sign-extended to 256 bits in two's complement, which is also how they are encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiValue {
+    Uint(U256),
+    Int(U256),
+    Address(U256),
+    Bool(bool),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<AbiValue>),
+    FixedArray(Vec<AbiValue>),
+    Tuple(Vec<AbiValue>),
+}
+
+impl AbiType {
+    // Parses a canonical type such as `uint8`, `bytes32[]` or `(address,uint256[2])[]`.
+    pub fn parse(ty: &str) -> Result<AbiType, String> {
+        let ty: &str = ty.trim();
+
+        if let Some(inner) = ty.strip_suffix(']') {
+            let open: usize = inner.rfind('[').ok_or(format!("invalid type: {}", ty))?;
+            let element: AbiType = AbiType::parse(&inner[..open])?;
+            let size: &str = &inner[open + 1..];
+            if size.is_empty() {
+                return Ok(AbiType::Array(Box::new(element)));
+            }
+            let size: usize = size.parse::<usize>().map_err(|_| format!("invalid array size in {}", ty))?;
+            return Ok(AbiType::FixedArray(Box::new(element), size));
+        }
+
+        if let Some(inner) = ty.strip_prefix('(').and_then(|rest: &str| rest.strip_suffix(')')) {
+            let components: Vec<AbiType> = split_top_level(inner)?.iter()
+                .map(|component: &&str| AbiType::parse(component))
+                .collect::<Result<Vec<AbiType>, String>>()?;
+            return Ok(AbiType::Tuple(components));
+        }
+
+        match ty {
+            "address" => return Ok(AbiType::Address),
+            "bool" => return Ok(AbiType::Bool),
+            "string" => return Ok(AbiType::String),
+            "bytes" => return Ok(AbiType::Bytes),
+            "uint" => return Ok(AbiType::Uint(256)),
+            "int" => return Ok(AbiType::Int(256)),
+            _ => {},
+        }
+
+        if let Some(bits) = ty.strip_prefix("uint") {
+            return Ok(AbiType::Uint(parse_bits(ty, bits)?));
+        }
+        if let Some(bits) = ty.strip_prefix("int") {
+            return Ok(AbiType::Int(parse_bits(ty, bits)?));
+        }
+        if let Some(size) = ty.strip_prefix("bytes") {
+            let size: usize = size.parse::<usize>().map_err(|_| format!("unsupported type: {}", ty))?;
+            if !(1..=32).contains(&size) {
+                return Err(format!("invalid type: {}", ty));
+            }
+            return Ok(AbiType::FixedBytes(size));
+        }
+        Err(format!("unsupported type: {}", ty))
+    }
+
+    // Size of a static type in the head of the enclosing tuple; dynamic types only take up an offset there.
+    fn head_size(&self) -> usize {
+        match self {
+            _ if self.is_dynamic() => 32,
+            AbiType::FixedArray(element, size) => element.head_size() * size,
+            AbiType::Tuple(components) => components.iter().map(AbiType::head_size).sum(),
+            _ => 32,
+        }
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiType::Bytes | AbiType::String | AbiType::Array(_) => true,
+            AbiType::FixedArray(element, _) => element.is_dynamic(),
+            AbiType::Tuple(components) => components.iter().any(AbiType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    // Parses a command line literal for this type. Arrays are written `[1,2,3]` and tuples `(1,0xabc)`;
+    // strings may be quoted to keep commas and brackets inside them.
+    pub fn parse_value(&self, text: &str) -> Result<AbiValue, String> {
+        let text: &str = text.trim();
+        match self {
+            AbiType::Uint(bits) => {
+                let value: U256 = parse_uint(text)?;
+                if value.bits() > *bits {
+                    return Err(format!("{} does not fit in uint{}", text, bits));
+                }
+                Ok(AbiValue::Uint(value))
+            },
+
+            AbiType::Int(bits) => Ok(AbiValue::Int(parse_int(text, *bits)?)),
+
+            AbiType::Address => {
+                let raw: &str = text.strip_prefix("0x").ok_or(format!("invalid address: {}", text))?;
+                if raw.len() != 40 {
+                    return Err(format!("invalid address: {}", text));
+                }
+                let address: U256 = U256::from_str_radix(raw, 16).map_err(|_| format!("invalid address: {}", text))?;
+                Ok(AbiValue::Address(address))
+            },
+
+            AbiType::Bool => match text {
+                "true" | "1" => Ok(AbiValue::Bool(true)),
+                "false" | "0" => Ok(AbiValue::Bool(false)),
+                _ => Err(format!("invalid bool: {}", text)),
+            },
+
+            AbiType::FixedBytes(size) => {
+                let bytes: Vec<u8> = parse_hex(text)?;
+                if bytes.len() > *size {
+                    return Err(format!("{} does not fit in bytes{}", text, size));
+                }
+                let mut padded: Vec<u8> = bytes;
+                padded.resize(*size, 0);
+                Ok(AbiValue::FixedBytes(padded))
+            },
+
+            AbiType::Bytes => Ok(AbiValue::Bytes(parse_hex(text)?)),
+
+            AbiType::String => Ok(AbiValue::String(unquote(text))),
+
+            AbiType::Array(element) => {
+                let items: Vec<AbiValue> = parse_list(text, '[', ']')?.iter()
+                    .map(|item: &&str| element.parse_value(item))
+                    .collect::<Result<Vec<AbiValue>, String>>()?;
+                Ok(AbiValue::Array(items))
+            },
+
+            AbiType::FixedArray(element, size) => {
+                let items: Vec<AbiValue> = parse_list(text, '[', ']')?.iter()
+                    .map(|item: &&str| element.parse_value(item))
+                    .collect::<Result<Vec<AbiValue>, String>>()?;
+                if items.len() != *size {
+                    return Err(format!("expected {} elements for {}, got {}", size, self, items.len()));
+                }
+                Ok(AbiValue::FixedArray(items))
+            },
+
+            AbiType::Tuple(components) => {
+                let items: Vec<&str> = parse_list(text, '(', ')')?;
+                if items.len() != components.len() {
+                    return Err(format!("expected {} components for {}, got {}", components.len(), self, items.len()));
+                }
+                let values: Vec<AbiValue> = components.iter().zip(items.iter())
+                    .map(|(component, item)| component.parse_value(item))
+                    .collect::<Result<Vec<AbiValue>, String>>()?;
+                Ok(AbiValue::Tuple(values))
+            },
+        }
+    }
+}
+
+impl fmt::Display for AbiType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiType::Uint(bits) => write!(f, "uint{}", bits),
+            AbiType::Int(bits) => write!(f, "int{}", bits),
+            AbiType::Address => write!(f, "address"),
+            AbiType::Bool => write!(f, "bool"),
+            AbiType::FixedBytes(size) => write!(f, "bytes{}", size),
+            AbiType::Bytes => write!(f, "bytes"),
+            AbiType::String => write!(f, "string"),
+            AbiType::Array(element) => write!(f, "{}[]", element),
+            AbiType::FixedArray(element, size) => write!(f, "{}[{}]", element, size),
+            AbiType::Tuple(components) => {
+                let components: Vec<String> = components.iter().map(AbiType::to_string).collect();
+                write!(f, "({})", components.join(","))
+            },
+        }
+    }
+}
+
+impl AbiValue {
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiValue::Bytes(_) | AbiValue::String(_) | AbiValue::Array(_) => true,
+            AbiValue::FixedArray(items) | AbiValue::Tuple(items) => items.iter().any(AbiValue::is_dynamic),
+            _ => false,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            // numbers are strings so that values above 2^53 survive JSON parsers
+            AbiValue::Uint(_) | AbiValue::Int(_) => serde_json::Value::String(self.to_string()),
+            AbiValue::Address(_) | AbiValue::FixedBytes(_) | AbiValue::Bytes(_) => serde_json::Value::String(self.to_string()),
+            AbiValue::Bool(b) => serde_json::Value::Bool(*b),
+            AbiValue::String(s) => serde_json::Value::String(s.clone()),
+            AbiValue::Array(items) | AbiValue::FixedArray(items) | AbiValue::Tuple(items) => {
+                serde_json::Value::Array(items.iter().map(AbiValue::to_json).collect())
+            },
+        }
+    }
+
+    // Size of the value in the head of the enclosing tuple: an offset for dynamic values, the value itself otherwise.
+    fn head_size(&self) -> usize {
+        match self {
+            _ if self.is_dynamic() => 32,
+            AbiValue::FixedArray(items) | AbiValue::Tuple(items) => items.iter().map(AbiValue::head_size).sum(),
+            _ => 32,
+        }
+    }
+}
+
+impl fmt::Display for AbiValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiValue::Uint(v) => write!(f, "{}", v),
+            AbiValue::Int(v) if v.bit(255) => write!(f, "-{}", (!*v).overflowing_add(U256::one()).0),
+            AbiValue::Int(v) => write!(f, "{}", v),
+            AbiValue::Address(v) => write!(f, "0x{}", hex::encode(&word(*v)[12..])),
+            AbiValue::Bool(b) => write!(f, "{}", b),
+            AbiValue::FixedBytes(bytes) | AbiValue::Bytes(bytes) => write!(f, "0x{}", hex::encode(bytes)),
+            AbiValue::String(s) => write!(f, "{:?}", s),
+            AbiValue::Array(items) | AbiValue::FixedArray(items) => write!(f, "[{}]", join(items)),
+            AbiValue::Tuple(items) => write!(f, "({})", join(items)),
+        }
+    }
+}
+
+fn join(items: &[AbiValue]) -> String {
+    items.iter().map(AbiValue::to_string).collect::<Vec<String>>().join(", ")
+}
+
+pub fn function_selector(signature: &str) -> [u8; 4] {
+    let hash: [u8; 32] = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut keccak: Keccak = Keccak::v256();
+    keccak.update(data);
+    let mut hash: [u8; 32] = [0u8; 32];
+    keccak.finalize(&mut hash);
+    hash
+}
+
+// Encodes values the way function arguments are: as the components of a single tuple.
+pub fn encode(values: &[AbiValue]) -> Vec<u8> {
+    let head_size: usize = values.iter().map(AbiValue::head_size).sum();
+    let mut head: Vec<u8> = Vec::with_capacity(head_size);
+    let mut tail: Vec<u8> = Vec::new();
+
+    for value in values {
+        if value.is_dynamic() {
+            head.extend_from_slice(&word(U256::from(head_size + tail.len())));
+            tail.extend(encode_value(value));
+        } else {
+            head.extend(encode_value(value));
+        }
+    }
+
+    head.extend(tail);
+    head
+}
+
+// Selector followed by the encoded arguments, each parsed from its literal according to `input_types`.
+pub fn encode_call(signature: &str, input_types: &[String], args: &[String]) -> Result<Vec<u8>, String> {
+    let encoded: Vec<u8> = encode_args(input_types, args).map_err(|e| format!("{}: {}", signature, e))?;
+    Ok([function_selector(signature).to_vec(), encoded].concat())
+}
+
+// Arguments without a selector, as constructor arguments are appended to the initcode.
+pub fn encode_args(input_types: &[String], args: &[String]) -> Result<Vec<u8>, String> {
+    if args.len() != input_types.len() {
+        return Err(format!("expected {} arguments, got {}", input_types.len(), args.len()));
+    }
+
+    let values: Vec<AbiValue> = input_types.iter().zip(args.iter())
+        .map(|(ty, arg)| AbiType::parse(ty)?.parse_value(arg))
+        .collect::<Result<Vec<AbiValue>, String>>()?;
+    Ok(encode(&values))
+}
+
+// Decodes data laid out as a tuple of `types`, such as return data. Offsets and lengths are checked against
+// the data, and values that do not fit their type are rejected rather than truncated.
+pub fn decode(types: &[AbiType], data: &[u8]) -> Result<Vec<AbiValue>, String> {
+    let mut values: Vec<AbiValue> = Vec::with_capacity(types.len());
+    let mut head: usize = 0;
+
+    for ty in types {
+        if ty.is_dynamic() {
+            let offset: usize = read_length(data, head)?;
+            values.push(decode_value(ty, data, offset)?);
+        } else {
+            values.push(decode_value(ty, data, head)?);
+        }
+        head += ty.head_size();
+    }
+    Ok(values)
+}
+
+fn decode_value(ty: &AbiType, data: &[u8], at: usize) -> Result<AbiValue, String> {
+    match ty {
+        AbiType::Uint(bits) => {
+            let value: U256 = read_word(data, at)?;
+            if value.bits() > *bits {
+                return Err(format!("value out of range for uint{}: {:#x}", bits, value));
+            }
+            Ok(AbiValue::Uint(value))
+        },
+
+        AbiType::Int(bits) => {
+            let value: U256 = read_word(data, at)?;
+            // every bit above the sign bit has to be a copy of it
+            let high: U256 = if *bits == 256 { U256::zero() } else { value >> (bits - 1) };
+            let all_ones: U256 = if *bits == 256 { U256::zero() } else { U256::MAX >> (bits - 1) };
+            if !high.is_zero() && high != all_ones {
+                return Err(format!("value out of range for int{}: {:#x}", bits, value));
+            }
+            Ok(AbiValue::Int(value))
+        },
+
+        AbiType::Address => {
+            let value: U256 = read_word(data, at)?;
+            if value.bits() > 160 {
+                return Err(format!("value out of range for address: {:#x}", value));
+            }
+            Ok(AbiValue::Address(value))
+        },
+
+        AbiType::Bool => match read_word(data, at)? {
+            value if value.is_zero() => Ok(AbiValue::Bool(false)),
+            value if value == U256::one() => Ok(AbiValue::Bool(true)),
+            value => Err(format!("value out of range for bool: {:#x}", value)),
+        },
+
+        AbiType::FixedBytes(size) => Ok(AbiValue::FixedBytes(read_bytes(data, at, *size)?.to_vec())),
+
+        AbiType::Bytes => {
+            let len: usize = read_length(data, at)?;
+            Ok(AbiValue::Bytes(read_bytes(data, at + 32, len)?.to_vec()))
+        },
+
+        AbiType::String => {
+            let len: usize = read_length(data, at)?;
+            let bytes: &[u8] = read_bytes(data, at + 32, len)?;
+            let s: &str = std::str::from_utf8(bytes).map_err(|_| "invalid utf-8 in string".to_string())?;
+            Ok(AbiValue::String(s.to_string()))
+        },
+
+        AbiType::Array(element) => {
+            let len: usize = read_length(data, at)?;
+            let items: &[u8] = data.get(at + 32..).ok_or("array data out of bounds".to_string())?;
+            // every element takes up at least a word, which bounds lengths before anything is allocated
+            if len > items.len() / 32 {
+                return Err(format!("array length {} exceeds the data", len));
+            }
+            Ok(AbiValue::Array(decode(&vec![(**element).clone(); len], items)?))
+        },
+
+        AbiType::FixedArray(element, size) => {
+            let items: &[u8] = data.get(at..).ok_or("array data out of bounds".to_string())?;
+            Ok(AbiValue::FixedArray(decode(&vec![(**element).clone(); *size], items)?))
+        },
+
+        AbiType::Tuple(components) => {
+            let items: &[u8] = data.get(at..).ok_or("tuple data out of bounds".to_string())?;
+            Ok(AbiValue::Tuple(decode(components, items)?))
+        },
+    }
+}
+
+fn read_word(data: &[u8], at: usize) -> Result<U256, String> {
+    Ok(U256::from_big_endian(read_bytes(data, at, 32)?))
+}
+
+// Offsets and lengths can never point past the end of the data, so anything larger is rejected.
+fn read_length(data: &[u8], at: usize) -> Result<usize, String> {
+    let value: U256 = read_word(data, at)?;
+    if value > U256::from(data.len()) {
+        return Err(format!("offset or length {:#x} at {:#x} exceeds the data", value, at));
+    }
+    Ok(value.as_usize())
+}
+
+fn read_bytes(data: &[u8], at: usize, len: usize) -> Result<&[u8], String> {
+    at.checked_add(len)
+        .and_then(|end: usize| data.get(at..end))
+        .ok_or(format!("data too short: need {} bytes at {:#x}, have {}", len, at, data.len()))
+}
+
+fn encode_value(value: &AbiValue) -> Vec<u8> {
+    match value {
+        AbiValue::Uint(v) | AbiValue::Int(v) | AbiValue::Address(v) => word(*v).to_vec(),
+        AbiValue::Bool(b) => word(U256::from(*b as u8)).to_vec(),
+        AbiValue::FixedBytes(bytes) => pad_right(bytes),
+        AbiValue::Bytes(bytes) => [word(U256::from(bytes.len())).to_vec(), pad_right(bytes)].concat(),
+        AbiValue::String(s) => [word(U256::from(s.len())).to_vec(), pad_right(s.as_bytes())].concat(),
+        AbiValue::Array(items) => [word(U256::from(items.len())).to_vec(), encode(items)].concat(),
+        AbiValue::FixedArray(items) | AbiValue::Tuple(items) => encode(items),
+    }
+}
+
+fn word(value: U256) -> [u8; 32] {
+    let mut buf: [u8; 32] = [0u8; 32];
+    value.to_big_endian(&mut buf);
+    buf
+}
+
+fn pad_right(bytes: &[u8]) -> Vec<u8> {
+    let mut padded: Vec<u8> = bytes.to_vec();
+    padded.resize(bytes.len().div_ceil(32) * 32, 0);
+    padded
+}
+
+fn parse_bits(ty: &str, bits: &str) -> Result<usize, String> {
+    let bits: usize = bits.parse::<usize>().map_err(|_| format!("unsupported type: {}", ty))?;
+    if bits == 0 || bits > 256 || !bits.is_multiple_of(8) {
+        return Err(format!("invalid type: {}", ty));
+    }
+    Ok(bits)
+}
+
+// Unsigned number literals: decimal or 0x hex, scientific notation (`1e18`, `1.5e9`) and an optional
+// `wei`, `gwei` or `ether` unit (`1 ether`, `2.5gwei`). The result has to be a whole number of wei.
+pub fn parse_uint(text: &str) -> Result<U256, String> {
+    let invalid = || format!("invalid number: {} (expected decimal, 0x hex, 1e18 or a unit like 1 ether)", text);
+    let trimmed: &str = text.trim();
+    let lower: String = trimmed.to_ascii_lowercase();
+
+    let (number, unit_decimals) = [("ether", 18), ("gwei", 9), ("wei", 0)].iter()
+        .find_map(|(unit, decimals)| lower.strip_suffix(unit).map(|number: &str| (number.trim_end(), *decimals)))
+        .unwrap_or((lower.as_str(), 0));
+    if number.is_empty() {
+        return Err(invalid());
+    }
+
+    if let Some(hex) = number.strip_prefix("0x") {
+        if hex.is_empty() {
+            return Err(invalid());
+        }
+        let value: U256 = U256::from_str_radix(hex, 16).map_err(|_| invalid())?;
+        return scale(value, unit_decimals).ok_or(format!("{} does not fit in 256 bits", text));
+    }
+
+    let (mantissa, exponent) = match number.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().map_err(|_| invalid())?),
+        None => (number, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+    if !whole.chars().chain(fraction.chars()).all(|c: char| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    // the digits without the decimal point, shifted by what the point, the exponent and the unit add up to
+    let mut digits: String = format!("{}{}", whole, fraction).trim_start_matches('0').to_string();
+    let mut shift: i64 = exponent.saturating_add(unit_decimals as i64).saturating_sub(fraction.len() as i64);
+    while shift < 0 && digits.ends_with('0') {
+        digits.pop();
+        shift += 1;
+    }
+    if digits.is_empty() {
+        return Ok(U256::zero());
+    }
+    if shift < 0 {
+        return Err(format!("{} is not a whole number", text));
+    }
+
+    let value: U256 = U256::from_dec_str(&digits).map_err(|_| format!("{} does not fit in 256 bits", text))?;
+    scale(value, shift as u64).ok_or(format!("{} does not fit in 256 bits", text))
+}
+
+fn scale(value: U256, decimals: u64) -> Option<U256> {
+    if value.is_zero() {
+        return Some(value);
+    }
+    if decimals > 77 {
+        return None;
+    }
+    value.checked_mul(U256::exp10(decimals as usize))
+}
+
+fn parse_int(text: &str, bits: usize) -> Result<U256, String> {
+    let (negative, magnitude) = match text.trim().strip_prefix('-') {
+        Some(rest) => (true, parse_uint(rest)?),
+        None => (false, parse_uint(text)?),
+    };
+
+    // intN holds -2^(N-1) ..= 2^(N-1) - 1
+    let limit: U256 = U256::one() << (bits - 1);
+    if (negative && magnitude > limit) || (!negative && magnitude >= limit) {
+        return Err(format!("{} does not fit in int{}", text, bits));
+    }
+
+    if negative {
+        Ok((!magnitude).overflowing_add(U256::one()).0)
+    } else {
+        Ok(magnitude)
+    }
+}
+
+fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let raw: &str = text.strip_prefix("0x").ok_or(format!("expected 0x-prefixed hex: {}", text))?;
+    hex::decode(raw).map_err(|_| format!("invalid hex: {}", text))
+}
+
+fn unquote(text: &str) -> String {
+    match text.strip_prefix('"').and_then(|rest: &str| rest.strip_suffix('"')) {
+        Some(inner) => inner.replace("\\\"", "\""),
+        None => text.to_string(),
+    }
+}
+
+fn parse_list(text: &str, open: char, close: char) -> Result<Vec<&str>, String> {
+    let inner: &str = text.strip_prefix(open)
+        .and_then(|rest: &str| rest.strip_suffix(close))
+        .ok_or(format!("expected {}...{}: {}", open, close, text))?;
+    split_top_level(inner)
+}
+
+// Splits on commas that are not nested inside brackets, parentheses or quotes.
+fn split_top_level(text: &str) -> Result<Vec<&str>, String> {
+    if text.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut parts: Vec<&str> = vec![];
+    let mut depth: i32 = 0;
+    let mut quoted: bool = false;
+    let mut escaped: bool = false;
+    let mut start: usize = 0;
+
+    for (i, c) in text.char_indices() {
+        if quoted {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => quoted = false,
+                _ => {},
+            }
+            continue;
+        }
+
+        match c {
+            '"' => quoted = true,
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(text[start..i].trim());
+                start = i + 1;
+            },
+            _ => {},
+        }
+        if depth < 0 {
+            return Err(format!("unbalanced brackets: {}", text));
+        }
+    }
+
+    if depth != 0 || quoted {
+        return Err(format!("unbalanced brackets or quotes: {}", text));
+    }
+    parts.push(text[start..].trim());
+    Ok(parts)
+}
@@ -0,0 +1,69 @@
+use primitive_types::U256;
+use super::{AbiType, AbiValue, decode};
+use super::json::{Abi, AbiError, format_call};
+
+// Error(string)
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+// Panic(uint256)
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+// Meaning of the codes the Solidity compiler raises `Panic(uint256)` with.
+pub fn panic_reason(code: U256) -> &'static str {
+    if code > U256::from(0xff) {
+        return "unknown panic code";
+    }
+    match code.low_u32() {
+        0x00 => "generic compiler panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic underflow or overflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "invalid encoded storage byte array",
+        0x31 => "pop on an empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to a zero-initialized function",
+        _ => "unknown panic code",
+    }
+}
+
+// Turns revert data into something readable: the message of `Error(string)`, the meaning of a
+// `Panic(uint256)` code, or a custom error from the ABI with its arguments. Anything else is shown as hex.
+pub fn decode_revert(data: &[u8], abi: &Abi) -> String {
+    if data.is_empty() {
+        return "execution reverted".to_string();
+    }
+    let raw: String = format!("execution reverted: 0x{}", hex::encode(data));
+    if data.len() < 4 {
+        return raw;
+    }
+
+    let selector: [u8; 4] = [data[0], data[1], data[2], data[3]];
+    let args: &[u8] = &data[4..];
+
+    match selector {
+        ERROR_SELECTOR => match decode(&[AbiType::String], args).as_deref() {
+            Ok([AbiValue::String(message)]) => format!("execution reverted: {}", message),
+            _ => raw,
+        },
+        PANIC_SELECTOR => match decode(&[AbiType::Uint(256)], args).as_deref() {
+            Ok([AbiValue::Uint(code)]) => format!("panic: {} ({:#04x})", panic_reason(*code), code),
+            _ => raw,
+        },
+        _ => {
+            let Some(error) = abi.error(selector) else {
+                return raw;
+            };
+            match decode_custom_error(error, args) {
+                Ok(decoded) => format!("execution reverted: {}", decoded),
+                Err(_) => raw,
+            }
+        },
+    }
+}
+
+fn decode_custom_error(error: &AbiError, args: &[u8]) -> Result<String, String> {
+    let types: Vec<AbiType> = error.inputs.iter().map(|param| param.ty.clone()).collect();
+    let values: Vec<AbiValue> = decode(&types, args)?;
+    Ok(format_call(&error.name, &error.inputs, &values))
+}
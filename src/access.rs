@@ -0,0 +1,133 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use primitive_types::U256;
+use crate::{ContractAccount, Evm, TxEnv, PRECOMPILES};
+use crate::inspector::{CallKind, Frame, Inspector};
+use crate::transaction::{TxResult, transact};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessListItem {
+    pub address: U256,
+    pub storage_keys: Vec<U256>,
+}
+
+// Addresses and storage slots touched so far in the transaction (EIP-2929).
+#[derive(Debug, Clone, Default)]
+pub struct AccessSet {
+    pub addresses: HashSet<U256>,
+    pub slots: HashSet<(U256, U256)>,
+}
+
+impl AccessSet {
+    // Marks the address as accessed, returning true if it was cold.
+    pub fn warm_address(&mut self, address: U256) -> bool {
+        self.addresses.insert(address)
+    }
+
+    // Marks the slot as accessed, returning true if it was cold.
+    pub fn warm_slot(&mut self, address: U256, key: U256) -> bool {
+        self.slots.insert((address, key))
+    }
+
+    pub fn is_address_warm(&self, address: U256) -> bool {
+        self.addresses.contains(&address)
+    }
+
+    pub fn is_slot_warm(&self, address: U256, key: U256) -> bool {
+        self.slots.contains(&(address, key))
+    }
+
+    pub fn apply(&mut self, access_list: &[AccessListItem]) {
+        for item in access_list {
+            self.warm_address(item.address);
+            for key in &item.storage_keys {
+                self.warm_slot(item.address, *key);
+            }
+        }
+    }
+}
+
+// Collects every address and storage slot touched during execution.
+#[derive(Default)]
+pub struct AccessListInspector {
+    excluded: HashSet<U256>,
+    touched: BTreeMap<U256, BTreeSet<U256>>,
+}
+
+impl AccessListInspector {
+    // Addresses in `excluded` (the sender and precompiles) are warm anyway and are left out.
+    pub fn new(excluded: HashSet<U256>) -> Self {
+        AccessListInspector { excluded, touched: BTreeMap::new() }
+    }
+
+    fn touch_address(&mut self, address: U256) {
+        if !self.excluded.contains(&address) {
+            self.touched.entry(address).or_default();
+        }
+    }
+
+    pub fn access_list(&self, recipient: U256) -> Vec<AccessListItem> {
+        self.touched.iter()
+            // the recipient is already warm, so it is only listed to carry its storage keys
+            .filter(|(address, keys)| **address != recipient || !keys.is_empty())
+            .map(|(address, keys)| AccessListItem {
+                address: *address,
+                storage_keys: keys.iter().copied().collect(),
+            })
+            .collect()
+    }
+}
+
+impl Inspector for AccessListInspector {
+    fn step(&mut self, evm: &Evm<'_>, opcode: u8) {
+        if let (0x31 | 0x3b | 0x3c | 0x3f, Some(address)) = (opcode, evm.stack.last()) {
+            self.touch_address(*address & ((U256::one() << 160) - 1));
+        }
+    }
+
+    fn frame_enter(&mut self, frame: &Frame) {
+        self.touch_address(frame.address);
+    }
+
+    fn sload(&mut self, address: U256, key: U256, _value: U256) {
+        self.touched.entry(address).or_default().insert(key);
+    }
+
+    fn sstore(&mut self, address: U256, key: U256, _old: U256, _new: U256) {
+        self.touched.entry(address).or_default().insert(key);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AccessListResult {
+    pub access_list: Vec<AccessListItem>,
+    pub gas_used: u64,
+    pub gas_used_without: u64,
+}
+
+// Equivalent of `eth_createAccessList`: runs the call against copies of the account, once to record
+// what it touches and once more with the resulting list. Gas figures are for the whole transaction, list included.
+pub fn create_access_list(account: &ContractAccount, callvalue: U256, calldata: Vec<u8>, env: &TxEnv) -> Result<AccessListResult, String> {
+    let mut excluded: HashSet<U256> = HashSet::from([env.caller]);
+    excluded.extend((1..=PRECOMPILES).map(U256::from));
+    let mut inspector: AccessListInspector = AccessListInspector::new(excluded);
+
+    let mut without: ContractAccount = account.clone();
+    let result: TxResult = transact(&mut without, CallKind::Call, callvalue, calldata.clone(), env, Some(&mut inspector));
+    result.result?;
+    let gas_used_without: u64 = result.gas_used;
+    let recipient: U256 = account.address;
+
+    let mut with_list: TxEnv = env.clone();
+    with_list.access_list = inspector.access_list(recipient);
+
+    let mut with: ContractAccount = account.clone();
+    let result: TxResult = transact(&mut with, CallKind::Call, callvalue, calldata, &with_list, None);
+    result.result?;
+    let gas_used: u64 = result.gas_used;
+
+    Ok(AccessListResult {
+        access_list: with_list.access_list,
+        gas_used,
+        gas_used_without,
+    })
+}
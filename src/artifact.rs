@@ -0,0 +1,233 @@
+use std::fs;
+use std::path::Path;
+use primitive_types::U256;
+use serde_json::Value;
+use crate::abi::Abi;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceMapEntry {
+    pub offset: i64,
+    pub length: i64,
+    pub file: i64,
+    pub jump: char,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageEntry {
+    pub label: String,
+    pub slot: U256,
+    pub offset: usize,
+    pub ty: String,
+}
+
+// A compiled contract, whichever tool produced it.
+#[derive(Debug, Clone, Default)]
+pub struct Artifact {
+    pub name: String,
+    pub abi: Abi,
+    pub bytecode: Vec<u8>,
+    pub deployed_bytecode: Vec<u8>,
+    pub source_map: Vec<SourceMapEntry>,
+    pub deployed_source_map: Vec<SourceMapEntry>,
+    pub storage_layout: Vec<StorageEntry>,
+}
+
+// Everything found at a path, along with the artifacts that were recognised but could not be loaded,
+// each as `<file or contract>: <reason>`.
+#[derive(Debug, Clone, Default)]
+pub struct LoadedArtifacts {
+    pub artifacts: Vec<Artifact>,
+    pub skipped: Vec<String>,
+}
+
+impl LoadedArtifacts {
+    fn add(&mut self, source: &str, artifact: Result<Artifact, String>) {
+        match artifact {
+            Ok(artifact) => self.artifacts.push(artifact),
+            Err(e) => self.skipped.push(format!("{}: {}", source, e)),
+        }
+    }
+}
+
+// Loads every contract from solc `--combined-json` output, a Foundry artifact (or its whole `out/`
+// directory) or a Hardhat artifact. The format is recognised from the shape of the JSON. A contract
+// that fails to load is skipped and reported, and loading only fails when nothing could be loaded.
+pub fn load_artifacts(path: &Path) -> Result<LoadedArtifacts, String> {
+    let mut loaded: LoadedArtifacts = LoadedArtifacts::default();
+    if path.is_dir() {
+        load_directory(path, &mut loaded)?;
+    } else if !load_file(path, &mut loaded)? {
+        return Err(format!("{} is not a solc, Foundry or Hardhat artifact", path.display()));
+    }
+
+    if loaded.artifacts.is_empty() && !loaded.skipped.is_empty() {
+        return Err(format!("no artifact could be loaded: {}", loaded.skipped.join("; ")));
+    }
+    Ok(loaded)
+}
+
+// Picks a contract by name, or the only one there is.
+pub fn select_artifact(artifacts: Vec<Artifact>, name: Option<&str>) -> Result<Artifact, String> {
+    match name {
+        Some(name) => artifacts.into_iter()
+            .find(|artifact: &Artifact| artifact.name == name)
+            .ok_or(format!("contract {} not found", name)),
+        None if artifacts.len() == 1 => Ok(artifacts.into_iter().next().expect("one artifact")),
+        None => {
+            let names: Vec<String> = artifacts.iter().map(|artifact: &Artifact| artifact.name.clone()).collect();
+            Err(format!("several contracts found, pick one of: {}", names.join(", ")))
+        },
+    }
+}
+
+// Foundry writes `out/<File>.sol/<Contract>.json`, next to `build-info` which is not an artifact.
+fn load_directory(dir: &Path, loaded: &mut LoadedArtifacts) -> Result<(), String> {
+    let mut entries: Vec<std::path::PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("could not read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            if entry.file_name().is_some_and(|name| name == "build-info") {
+                continue;
+            }
+            load_directory(&entry, loaded)?;
+        } else if entry.extension().is_some_and(|extension| extension == "json") {
+            // JSON in some other shape, such as a cache file, is passed over, while a file that cannot be
+            // read or parsed is reported
+            if let Err(e) = load_file(&entry, loaded) {
+                loaded.skipped.push(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Skips the contracts in the file that cannot be loaded, returning false when the file is not an artifact at all.
+fn load_file(path: &Path, loaded: &mut LoadedArtifacts) -> Result<bool, String> {
+    let contents: String = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let json: Value = serde_json::from_str(&contents).map_err(|e| format!("could not parse {}: {}", path.display(), e))?;
+    let stem: String = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+
+    if let Some(contracts) = json["contracts"].as_object() {
+        for (key, contract) in contracts {
+            loaded.add(key, from_combined_json(key, contract));
+        }
+    } else if json["_format"].as_str().is_some_and(|format: &str| format.starts_with("hh-sol-artifact")) {
+        loaded.add(&path.display().to_string(), from_hardhat(&json));
+    } else if json["bytecode"].is_object() {
+        loaded.add(&path.display().to_string(), from_foundry(&json, &stem));
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+// Keys are `path/to/File.sol:Name`.
+fn from_combined_json(key: &str, contract: &Value) -> Result<Artifact, String> {
+    let name: &str = key.rsplit(':').next().unwrap_or(key);
+    Ok(Artifact {
+        name: name.to_string(),
+        abi: parse_abi(&contract["abi"])?,
+        bytecode: parse_bytecode(&contract["bin"])?,
+        deployed_bytecode: parse_bytecode(&contract["bin-runtime"])?,
+        source_map: parse_source_map(contract["srcmap"].as_str().unwrap_or_default())?,
+        deployed_source_map: parse_source_map(contract["srcmap-runtime"].as_str().unwrap_or_default())?,
+        storage_layout: parse_storage_layout(&contract["storage-layout"])?,
+    })
+}
+
+fn from_foundry(json: &Value, stem: &str) -> Result<Artifact, String> {
+    Ok(Artifact {
+        name: stem.to_string(),
+        abi: parse_abi(&json["abi"])?,
+        bytecode: parse_bytecode(&json["bytecode"]["object"])?,
+        deployed_bytecode: parse_bytecode(&json["deployedBytecode"]["object"])?,
+        source_map: parse_source_map(json["bytecode"]["sourceMap"].as_str().unwrap_or_default())?,
+        deployed_source_map: parse_source_map(json["deployedBytecode"]["sourceMap"].as_str().unwrap_or_default())?,
+        storage_layout: parse_storage_layout(&json["storageLayout"])?,
+    })
+}
+
+// Hardhat keeps source maps and storage layouts in its build-info files, not in the artifact.
+fn from_hardhat(json: &Value) -> Result<Artifact, String> {
+    Ok(Artifact {
+        name: json["contractName"].as_str().unwrap_or_default().to_string(),
+        abi: parse_abi(&json["abi"])?,
+        bytecode: parse_bytecode(&json["bytecode"])?,
+        deployed_bytecode: parse_bytecode(&json["deployedBytecode"])?,
+        ..Artifact::default()
+    })
+}
+
+// Older solc versions put the ABI into combined-json as a string of JSON.
+fn parse_abi(json: &Value) -> Result<Abi, String> {
+    match json {
+        Value::String(s) => {
+            let parsed: Value = serde_json::from_str(s).map_err(|e| format!("invalid ABI: {}", e))?;
+            Abi::from_json(&parsed)
+        },
+        Value::Null => Ok(Abi::default()),
+        _ => Abi::from_json(json),
+    }
+}
+
+fn parse_bytecode(json: &Value) -> Result<Vec<u8>, String> {
+    let code: &str = json.as_str().unwrap_or_default();
+    let code: &str = code.strip_prefix("0x").unwrap_or(code);
+    if code.contains("__") {
+        return Err("bytecode has unlinked library references".to_string());
+    }
+    hex::decode(code).map_err(|e| format!("invalid bytecode: {}", e))
+}
+
+// Source maps are `s:l:f:j` entries separated by `;`, where an empty field repeats the one before it.
+pub fn parse_source_map(map: &str) -> Result<Vec<SourceMapEntry>, String> {
+    if map.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut entries: Vec<SourceMapEntry> = vec![];
+    let mut current: SourceMapEntry = SourceMapEntry { offset: 0, length: 0, file: -1, jump: '-' };
+
+    for entry in map.split(';') {
+        let fields: Vec<&str> = entry.split(':').collect();
+        let number = |i: usize, previous: i64| -> Result<i64, String> {
+            match fields.get(i) {
+                Some(field) if !field.is_empty() => field.parse::<i64>().map_err(|_| format!("invalid source map entry: {}", entry)),
+                _ => Ok(previous),
+            }
+        };
+
+        current.offset = number(0, current.offset)?;
+        current.length = number(1, current.length)?;
+        current.file = number(2, current.file)?;
+        if let Some(jump) = fields.get(3).and_then(|field: &&str| field.chars().next()) {
+            current.jump = jump;
+        }
+        entries.push(current.clone());
+    }
+    Ok(entries)
+}
+
+fn parse_storage_layout(json: &Value) -> Result<Vec<StorageEntry>, String> {
+    let Some(storage) = json["storage"].as_array() else {
+        return Ok(vec![]);
+    };
+
+    storage.iter()
+        .map(|entry: &Value| {
+            let type_id: &str = entry["type"].as_str().unwrap_or_default();
+            let slot: &str = entry["slot"].as_str().ok_or(format!("storage entry without a slot: {}", entry))?;
+            Ok(StorageEntry {
+                label: entry["label"].as_str().unwrap_or_default().to_string(),
+                slot: U256::from_dec_str(slot).map_err(|_| format!("invalid storage slot: {}", slot))?,
+                offset: entry["offset"].as_u64().unwrap_or(0) as usize,
+                // prefer the readable label from the types table over ids like `t_uint256`
+                ty: json["types"][type_id]["label"].as_str().unwrap_or(type_id).to_string(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,346 @@
+use std::fmt;
+use primitive_types::U256;
+use crate::{Evm, ExitReason, opcodes};
+use crate::journal::{StepDelta, StorageChange};
+
+const HELP: &str = "\
+step | s [n]            execute the next n instructions (default 1)
+next | n                step over the next instruction, running any call it makes to completion
+back | sb [n]           undo the last n instructions (default 1)
+goto | g <step>         move backwards or forwards to the given step number
+last-write | lw <n>     find the last instruction that wrote storage slot n
+continue | c            run until a breakpoint is hit or execution ends
+break | b pc <n>        break before the instruction at pc n
+break | b op <name>     break before any instruction with the given opcode (e.g. SSTORE or 0x55)
+break | b slot <n>      break before any SLOAD/SSTORE of storage slot n
+delete | d <index>      remove a breakpoint
+breakpoints | bl        list breakpoints
+where | w               show the current instruction
+stack                   print the stack, top first
+memory | mem            print a hex dump of memory
+storage                 print contract storage
+quit | q                leave the debugger";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breakpoint {
+    Pc(usize),
+    Opcode(u8),
+    Slot(U256),
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Pc(pc) => write!(f, "pc {:#x}", pc),
+            Breakpoint::Opcode(op) => write!(f, "op {}", opcodes::name(*op)),
+            Breakpoint::Slot(slot) => write!(f, "slot {:#x}", slot),
+        }
+    }
+}
+
+pub struct Debugger<'a> {
+    pub evm: Evm<'a>,
+    pub breakpoints: Vec<Breakpoint>,
+    pub exit: Option<Result<ExitReason, String>>,
+    pub journal: Vec<StepDelta>,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(evm: Evm<'a>) -> Self {
+        Debugger {
+            evm,
+            breakpoints: vec![],
+            exit: None,
+            journal: vec![],
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.exit.is_some()
+    }
+
+    pub fn current_opcode(&self) -> Option<u8> {
+        if self.finished() {
+            return None;
+        }
+        Some(*self.evm.code.get(self.evm.pc).unwrap_or(&0x00))
+    }
+
+    pub fn step(&mut self) {
+        if self.finished() {
+            return;
+        }
+
+        let mut delta: StepDelta = StepDelta::capture(&self.evm);
+        match self.evm.step() {
+            Ok(None) => {},
+            Ok(Some(exit)) => self.exit = Some(Ok(exit)),
+            Err(e) => self.exit = Some(Err(e)),
+        }
+        delta.complete(&self.evm);
+        self.journal.push(delta);
+    }
+
+    // Undoes the last executed instruction. Returns false if there is nothing left to undo.
+    pub fn step_back(&mut self) -> bool {
+        let Some(delta) = self.journal.pop() else {
+            return false;
+        };
+        delta.revert(&mut self.evm);
+        self.exit = None;
+        true
+    }
+
+    // Moves to the state after `step` instructions, replaying forwards or undoing backwards as needed.
+    pub fn goto(&mut self, step: usize) {
+        while self.journal.len() > step {
+            self.step_back();
+        }
+        while self.journal.len() < step && !self.finished() {
+            self.step();
+        }
+    }
+
+    // Returns the index of the last step that wrote `slot`, along with what it wrote.
+    pub fn last_write(&self, slot: U256) -> Option<(usize, &StepDelta)> {
+        self.journal.iter().enumerate().rev()
+            .find(|(_, delta)| delta.storage.as_ref().is_some_and(|change| change.key == slot))
+    }
+
+    // A call made by the next instruction runs to completion inside `Evm::step`,
+    // so stepping over it is the same as stepping into it.
+    pub fn step_over(&mut self) {
+        self.step();
+    }
+
+    // Runs until a breakpoint is hit, returning its index, or until execution ends.
+    pub fn resume(&mut self) -> Option<usize> {
+        self.step();
+        while !self.finished() {
+            if let Some(index) = self.hit_breakpoint() {
+                return Some(index);
+            }
+            self.step();
+        }
+        None
+    }
+
+    fn hit_breakpoint(&self) -> Option<usize> {
+        let opcode: u8 = self.current_opcode()?;
+        self.breakpoints.iter().position(|breakpoint: &Breakpoint| match breakpoint {
+            Breakpoint::Pc(pc) => *pc == self.evm.pc,
+            Breakpoint::Opcode(op) => *op == opcode,
+            Breakpoint::Slot(slot) => {
+                (opcode == 0x54 || opcode == 0x55) && self.evm.stack.last() == Some(slot)
+            },
+        })
+    }
+
+    pub fn location(&self) -> String {
+        match &self.exit {
+            Some(Ok(exit)) => format!("execution finished: {}", describe_exit(exit)),
+            Some(Err(e)) => format!("execution failed: {}", e),
+            None => {
+                let opcode: u8 = self.current_opcode().unwrap_or(0x00);
+                let mut instruction: String = opcodes::name(opcode).to_string();
+
+                if (0x60..=0x7f).contains(&opcode) {
+                    let start: usize = (self.evm.pc + 1).min(self.evm.code.len());
+                    let end: usize = (start + (opcode - 0x5f) as usize).min(self.evm.code.len());
+                    instruction = format!("{} 0x{}", instruction, hex::encode(&self.evm.code[start..end]));
+                }
+                format!("step {}: pc={:#06x} {} gas={}", self.journal.len(), self.evm.pc, instruction, self.evm.gas_remaining())
+            },
+        }
+    }
+
+    pub fn stack_dump(&self) -> String {
+        if self.evm.stack.is_empty() {
+            return "<empty stack>".to_string();
+        }
+
+        self.evm.stack.iter().rev().enumerate()
+            .map(|(i, value)| format!("{:>3}: {:#x}", i, value))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn memory_dump(&self) -> String {
+        if self.evm.memory.is_empty() {
+            return "<empty memory>".to_string();
+        }
+
+        self.evm.memory.chunks(32).enumerate()
+            .map(|(i, word)| format!("{:#06x}: {}", i * 32, hex::encode(word)))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn storage_dump(&self) -> String {
+        if self.evm.storage.is_empty() {
+            return "<empty storage>".to_string();
+        }
+
+        let mut slots: Vec<(&U256, &U256)> = self.evm.storage.iter().collect();
+        slots.sort();
+        slots.iter()
+            .map(|(key, value)| format!("{:#x}: {:#x}", key, value))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // Runs a single debugger command and returns the text to show the user.
+    pub fn command(&mut self, line: &str) -> String {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let Some(cmd) = parts.first() else {
+            return self.location();
+        };
+
+        match *cmd {
+            "step" | "s" => {
+                let count: usize = match parse_count(parts.get(1)) {
+                    Ok(count) => count,
+                    Err(e) => return e,
+                };
+                for _ in 0..count {
+                    self.step();
+                }
+                self.location()
+            },
+
+            "next" | "n" => {
+                self.step_over();
+                self.location()
+            },
+
+            "back" | "sb" => {
+                let count: usize = match parse_count(parts.get(1)) {
+                    Ok(count) => count,
+                    Err(e) => return e,
+                };
+                for _ in 0..count {
+                    if !self.step_back() {
+                        break;
+                    }
+                }
+                self.location()
+            },
+
+            "goto" | "g" => match parts.get(1).and_then(|n: &&str| n.parse::<usize>().ok()) {
+                Some(step) => {
+                    self.goto(step);
+                    self.location()
+                },
+                None => "usage: goto <step>".to_string(),
+            },
+
+            "last-write" | "lw" => {
+                let slot: U256 = match parts.get(1).map(|n: &&str| parse_number(n)) {
+                    Some(Ok(slot)) => slot,
+                    Some(Err(e)) => return e,
+                    None => return "usage: last-write <slot>".to_string(),
+                };
+                match self.last_write(slot) {
+                    Some((step, delta)) => {
+                        let change: &StorageChange = delta.storage.as_ref().expect("last_write only returns storage writes");
+                        format!("step {}: pc={:#06x} wrote slot {:#x}: {:#x} -> {:#x}", step, delta.pc, slot, change.old, change.new)
+                    },
+                    None => format!("slot {:#x} has not been written", slot),
+                }
+            },
+
+            "continue" | "c" => match self.resume() {
+                Some(index) => format!("breakpoint {} ({})\n{}", index, self.breakpoints[index], self.location()),
+                None => self.location(),
+            },
+
+            "break" | "b" => match parse_breakpoint(&parts[1..]) {
+                Ok(breakpoint) => {
+                    let message: String = format!("breakpoint {} set at {}", self.breakpoints.len(), breakpoint);
+                    self.breakpoints.push(breakpoint);
+                    message
+                },
+                Err(e) => e,
+            },
+
+            "delete" | "d" => match parts.get(1).and_then(|n: &&str| n.parse::<usize>().ok()) {
+                Some(index) if index < self.breakpoints.len() => {
+                    format!("deleted breakpoint {} ({})", index, self.breakpoints.remove(index))
+                },
+                _ => "usage: delete <index>".to_string(),
+            },
+
+            "breakpoints" | "bl" => {
+                if self.breakpoints.is_empty() {
+                    return "no breakpoints".to_string();
+                }
+                self.breakpoints.iter().enumerate()
+                    .map(|(i, breakpoint)| format!("{}: {}", i, breakpoint))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            },
+
+            "where" | "w" => self.location(),
+            "stack" => self.stack_dump(),
+            "memory" | "mem" => self.memory_dump(),
+            "storage" => self.storage_dump(),
+            "help" | "h" => HELP.to_string(),
+            _ => format!("unknown command: {} (try 'help')", cmd),
+        }
+    }
+}
+
+pub fn describe_exit(exit: &ExitReason) -> String {
+    match exit {
+        ExitReason::Return(data) => format!("Return(0x{})", hex::encode(data)),
+        ExitReason::Revert(data) => format!("Revert(0x{})", hex::encode(data)),
+        ExitReason::Stop => "Stop".to_string(),
+    }
+}
+
+fn parse_breakpoint(args: &[&str]) -> Result<Breakpoint, String> {
+    let usage: String = "usage: break <pc|op|slot> <value>".to_string();
+    let (Some(kind), Some(value)) = (args.first(), args.get(1)) else {
+        return Err(usage);
+    };
+
+    match *kind {
+        "pc" => {
+            let pc: U256 = parse_number(value)?;
+            if pc.bits() > usize::BITS as usize {
+                return Err(format!("invalid pc: {}", value));
+            }
+            Ok(Breakpoint::Pc(pc.as_usize()))
+        },
+        "op" => {
+            let by_name: Option<u8> = (0..=255u8).find(|op: &u8| opcodes::name(*op).eq_ignore_ascii_case(value));
+            match by_name {
+                Some(op) => Ok(Breakpoint::Opcode(op)),
+                None => {
+                    let op: U256 = parse_number(value)?;
+                    if op > U256::from(0xff) {
+                        return Err(format!("invalid opcode: {}", value));
+                    }
+                    Ok(Breakpoint::Opcode(op.low_u32() as u8))
+                },
+            }
+        },
+        "slot" => Ok(Breakpoint::Slot(parse_number(value)?)),
+        _ => Err(usage),
+    }
+}
+
+fn parse_count(arg: Option<&&str>) -> Result<usize, String> {
+    match arg {
+        Some(n) => n.parse::<usize>().map_err(|_| format!("invalid step count: {}", n)),
+        None => Ok(1),
+    }
+}
+
+fn parse_number(value: &str) -> Result<U256, String> {
+    let parsed: Option<U256> = match value.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok(),
+        None => U256::from_dec_str(value).ok(),
+    };
+    parsed.ok_or(format!("invalid number: {}", value))
+}
@@ -0,0 +1,51 @@
+use primitive_types::U256;
+use crate::{ContractAccount, ExitReason, TxEnv, gas};
+use crate::inspector::CallKind;
+use crate::transaction::{TxResult, transact};
+
+// Runs the call with the given gas limit against a copy of the account, so nothing is ever committed.
+fn attempt(account: &ContractAccount, callvalue: U256, calldata: &[u8], env: &TxEnv, gas_limit: u64) -> TxResult {
+    let mut snapshot: ContractAccount = account.clone();
+    let mut env: TxEnv = env.clone();
+    env.gas_limit = gas_limit;
+
+    transact(&mut snapshot, CallKind::Call, callvalue, calldata.to_vec(), &env, None)
+}
+
+// Finds the lowest gas limit the transaction succeeds with, intrinsic gas included, by binary search
+// between the gas it was seen to need and `env.gas_limit`. Refunds only arrive once execution ends
+// and SSTORE fails unless more than the call stipend is left, so the limit needed is usually higher
+// than the gas the call ends up using.
+pub fn estimate_gas(account: &ContractAccount, callvalue: U256, calldata: &[u8], env: &TxEnv) -> Result<u64, String> {
+    let cap: TxResult = attempt(account, callvalue, calldata, env, env.gas_limit);
+    match &cap.result {
+        Ok(ExitReason::Revert(_)) => return Err("execution reverted".to_string()),
+        Err(e) => return Err(e.clone()),
+        _ => {},
+    }
+
+    // gas that was refunded was still needed while the call ran, and the calldata floor has to be covered up front
+    let needed: u64 = (cap.intrinsic_gas + cap.execution_gas).max(cap.floor_gas);
+    let mut lo: u64 = needed.saturating_sub(1);
+    let mut hi: u64 = env.gas_limit;
+
+    // most calls succeed with the stipend SSTORE keeps back added to what they used, saving most of the search
+    let optimistic: u64 = needed + gas::SSTORE_SENTRY;
+    if optimistic < hi {
+        if attempt(account, callvalue, calldata, env, optimistic).success() {
+            hi = optimistic;
+        } else {
+            lo = optimistic;
+        }
+    }
+
+    while hi - lo > 1 {
+        let mid: u64 = lo + (hi - lo) / 2;
+        if attempt(account, callvalue, calldata, env, mid).success() {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Ok(hi)
+}
@@ -0,0 +1,175 @@
+use primitive_types::U256;
+use crate::access::AccessListItem;
+
+pub const DEFAULT_GAS_LIMIT: u64 = 30_000_000;
+
+const TX_BASE: u64 = 21_000;
+const TX_CREATE: u64 = 32_000;
+const INITCODE_WORD: u64 = 2;
+const TOKENS_PER_NON_ZERO_BYTE: u64 = 4;
+const STANDARD_TOKEN_COST: u64 = 4;
+const TOTAL_COST_FLOOR_PER_TOKEN: u64 = 10;
+
+const ZERO: u64 = 0;
+const JUMPDEST: u64 = 1;
+const BASE: u64 = 2;
+const VERY_LOW: u64 = 3;
+const LOW: u64 = 5;
+const MID: u64 = 8;
+const HIGH: u64 = 10;
+const KECCAK256: u64 = 30;
+const KECCAK256_WORD: u64 = 6;
+const COPY_WORD: u64 = 3;
+const EXP_BYTE: u64 = 50;
+const MEMORY_WORD: u64 = 3;
+const COLD_SLOAD: u64 = 2_100;
+const COLD_ACCOUNT_ACCESS: u64 = 2_600;
+const ACCESS_LIST_ADDRESS: u64 = 2_400;
+const ACCESS_LIST_STORAGE_KEY: u64 = 1_900;
+const WARM_STORAGE_READ: u64 = 100;
+const SSTORE_SET: u64 = 20_000;
+const SSTORE_RESET: u64 = 2_900;
+const SSTORE_CLEARS_SCHEDULE: i64 = 4_800;
+const MAX_REFUND_QUOTIENT: u64 = 5;
+pub const SSTORE_SENTRY: u64 = 2_300;
+const LOG: u64 = 375;
+const LOG_TOPIC: u64 = 375;
+const LOG_DATA_BYTE: u64 = 8;
+
+pub fn static_cost(opcode: u8) -> u64 {
+    match opcode {
+        0x00 | 0xf3 | 0xfd => ZERO,
+        0x01 | 0x03 => VERY_LOW,
+        0x02 | 0x04..=0x07 | 0x0b => LOW,
+        0x08 | 0x09 => MID,
+        0x0A => HIGH,
+        0x10..=0x1d => VERY_LOW,
+        0x20 => KECCAK256,
+        0x30 | 0x32..=0x34 | 0x36 | 0x38 | 0x3a | 0x3d => BASE,
+        0x31 | 0x3b | 0x3c | 0x3f => ZERO,
+        0x35 | 0x37 | 0x39 | 0x3e => VERY_LOW,
+        0x41..=0x46 | 0x48 | 0x4a => BASE,
+        0x47 => LOW,
+        0x50 => BASE,
+        0x51..=0x53 => VERY_LOW,
+        0x54 | 0x55 => ZERO,
+        0x56 => MID,
+        0x57 => HIGH,
+        0x58..=0x5a => BASE,
+        0x5b => JUMPDEST,
+        0x5e => VERY_LOW,
+        0x5f => BASE,
+        0x60..=0x9f => VERY_LOW,
+        0xa0..=0xa4 => LOG,
+        _ => ZERO,
+    }
+}
+
+// Total cost of a memory of `words` 32-byte words; expansion charges the difference.
+pub fn memory_cost(words: usize) -> u64 {
+    let words: u64 = words as u64;
+    MEMORY_WORD * words + words * words / 512
+}
+
+pub fn copy_cost(size: usize) -> u64 {
+    COPY_WORD * size.div_ceil(32) as u64
+}
+
+pub fn keccak256_cost(size: usize) -> u64 {
+    KECCAK256_WORD * size.div_ceil(32) as u64
+}
+
+pub fn exp_cost(exponent: U256) -> u64 {
+    EXP_BYTE * exponent.bits().div_ceil(8) as u64
+}
+
+pub fn log_cost(topics: usize, size: usize) -> u64 {
+    LOG_TOPIC * topics as u64 + LOG_DATA_BYTE * size as u64
+}
+
+// Warm and cold access pricing (EIP-2929).
+pub fn sload_cost(cold: bool) -> u64 {
+    if cold { COLD_SLOAD } else { WARM_STORAGE_READ }
+}
+
+pub fn account_access_cost(cold: bool) -> u64 {
+    if cold { COLD_ACCOUNT_ACCESS } else { WARM_STORAGE_READ }
+}
+
+// Calldata tokens as defined by EIP-7623: zero bytes count once, non-zero bytes four times.
+pub fn calldata_tokens(data: &[u8]) -> u64 {
+    data.iter()
+        .map(|byte: &u8| if *byte == 0 { 1 } else { TOKENS_PER_NON_ZERO_BYTE })
+        .sum()
+}
+
+// Gas charged before execution starts: the base fee, calldata (4/16 per zero/non-zero byte),
+// contract creation with its initcode words (EIP-3860) and the access list (EIP-2930).
+pub fn intrinsic_gas(data: &[u8], is_create: bool, access_list: &[AccessListItem]) -> u64 {
+    let mut gas: u64 = TX_BASE + STANDARD_TOKEN_COST * calldata_tokens(data);
+    if is_create {
+        gas += TX_CREATE + INITCODE_WORD * data.len().div_ceil(32) as u64;
+    }
+    gas + access_list_cost(access_list)
+}
+
+// Least gas a transaction can be charged for its calldata since Prague (EIP-7623).
+pub fn floor_gas(data: &[u8]) -> u64 {
+    TX_BASE + TOTAL_COST_FLOOR_PER_TOKEN * calldata_tokens(data)
+}
+
+// Up-front cost of an EIP-2930 access list.
+pub fn access_list_cost(access_list: &[AccessListItem]) -> u64 {
+    access_list.iter()
+        .map(|item: &AccessListItem| ACCESS_LIST_ADDRESS + ACCESS_LIST_STORAGE_KEY * item.storage_keys.len() as u64)
+        .sum()
+}
+
+// Net-metered SSTORE (EIP-2200 with the EIP-3529 refund schedule), returning the cost and the refund delta.
+// `original` is the value of the slot at the start of the transaction.
+pub fn sstore_cost(original: U256, current: U256, new: U256, cold: bool) -> (u64, i64) {
+    let (cost, refund) = sstore_warm_cost(original, current, new);
+    if cold {
+        return (cost + COLD_SLOAD, refund);
+    }
+    (cost, refund)
+}
+
+fn sstore_warm_cost(original: U256, current: U256, new: U256) -> (u64, i64) {
+    if current == new {
+        return (WARM_STORAGE_READ, 0);
+    }
+
+    if original == current {
+        if original.is_zero() {
+            return (SSTORE_SET, 0);
+        }
+        let refund: i64 = if new.is_zero() { SSTORE_CLEARS_SCHEDULE } else { 0 };
+        return (SSTORE_RESET, refund);
+    }
+
+    // the slot is already dirty in this transaction
+    let mut refund: i64 = 0;
+    if !original.is_zero() {
+        if current.is_zero() {
+            refund -= SSTORE_CLEARS_SCHEDULE;
+        }
+        if new.is_zero() {
+            refund += SSTORE_CLEARS_SCHEDULE;
+        }
+    }
+
+    if original == new {
+        refund += if original.is_zero() {
+            (SSTORE_SET - WARM_STORAGE_READ) as i64
+        } else {
+            (SSTORE_RESET - WARM_STORAGE_READ) as i64
+        };
+    }
+    (WARM_STORAGE_READ, refund)
+}
+
+// Refunds are capped at a fifth of the gas used since London (EIP-3529).
+pub fn capped_refund(refund: i64, gas_used: u64) -> u64 {
+    (refund.max(0) as u64).min(gas_used / MAX_REFUND_QUOTIENT)
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default)]
+pub struct FunctionGas {
+    pub name: String,
+    pub calls: Vec<u64>,
+}
+
+impl FunctionGas {
+    pub fn min(&self) -> u64 {
+        self.calls.iter().copied().min().unwrap_or(0)
+    }
+
+    pub fn max(&self) -> u64 {
+        self.calls.iter().copied().max().unwrap_or(0)
+    }
+
+    pub fn mean(&self) -> u64 {
+        if self.calls.is_empty() {
+            return 0;
+        }
+        self.calls.iter().sum::<u64>() / self.calls.len() as u64
+    }
+
+    // the upper median for an even number of calls, as forge reports it
+    pub fn median(&self) -> u64 {
+        let mut sorted: Vec<u64> = self.calls.clone();
+        sorted.sort();
+        sorted.get(sorted.len() / 2).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ContractGas {
+    pub deployment_gas: Option<u64>,
+    pub deployment_size: usize,
+    pub functions: BTreeMap<[u8; 4], FunctionGas>,
+}
+
+// Gas used per contract and per function selector, aggregated over a session like `forge test --gas-report`.
+#[derive(Debug, Clone, Default)]
+pub struct GasReport {
+    pub contracts: BTreeMap<String, ContractGas>,
+}
+
+impl GasReport {
+    pub fn new() -> Self {
+        GasReport::default()
+    }
+
+    pub fn record_deployment(&mut self, contract: &str, gas_used: u64, code_size: usize) {
+        let entry: &mut ContractGas = self.contracts.entry(contract.to_string()).or_default();
+        entry.deployment_gas = Some(gas_used);
+        entry.deployment_size = code_size;
+    }
+
+    pub fn record_call(&mut self, contract: &str, selector: [u8; 4], name: &str, gas_used: u64) {
+        let function: &mut FunctionGas = self.contracts.entry(contract.to_string()).or_default()
+            .functions.entry(selector).or_default();
+        if function.name.is_empty() {
+            function.name = name.to_string();
+        }
+        function.calls.push(gas_used);
+    }
+
+    pub fn table(&self) -> String {
+        let mut lines: Vec<String> = vec![];
+
+        for (contract, gas) in &self.contracts {
+            lines.push(format!("| {} contract", contract));
+            if let Some(deployment_gas) = gas.deployment_gas {
+                lines.push(format!("| Deployment cost: {} | Deployment size: {}", deployment_gas, gas.deployment_size));
+            }
+
+            let name_width: usize = gas.functions.values()
+                .map(|function: &FunctionGas| function.name.len())
+                .max()
+                .unwrap_or(0)
+                .max("Function".len());
+
+            lines.push(format!("| {:<name_width$} | {:<8} | {:>8} | {:>8} | {:>8} | {:>8} | {:>7} |",
+                "Function", "Selector", "min", "mean", "median", "max", "# calls"));
+            for (selector, function) in &gas.functions {
+                lines.push(format!("| {:<name_width$} | {:<8} | {:>8} | {:>8} | {:>8} | {:>8} | {:>7} |",
+                    function.name, hex::encode(selector), function.min(), function.mean(), function.median(), function.max(), function.calls.len()));
+            }
+            lines.push(String::new());
+        }
+
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let contracts: serde_json::Map<String, serde_json::Value> = self.contracts.iter()
+            .map(|(contract, gas)| {
+                let functions: serde_json::Map<String, serde_json::Value> = gas.functions.iter()
+                    .map(|(selector, function)| (format!("0x{}", hex::encode(selector)), serde_json::json!({
+                        "name": function.name,
+                        "min": function.min(),
+                        "mean": function.mean(),
+                        "median": function.median(),
+                        "max": function.max(),
+                        "calls": function.calls.len(),
+                    })))
+                    .collect();
+
+                (contract.clone(), serde_json::json!({
+                    "deploymentCost": gas.deployment_gas,
+                    "deploymentSize": gas.deployment_size,
+                    "functions": functions,
+                }))
+            })
+            .collect();
+
+        serde_json::Value::Object(contracts)
+    }
+}
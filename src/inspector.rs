@@ -0,0 +1,75 @@
+use primitive_types::U256;
+use crate::{Evm, ExitReason, Log};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    Call,
+    Create,
+}
+
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub kind: CallKind,
+    pub caller: U256,
+    pub address: U256,
+    pub value: U256,
+    pub input: Vec<u8>,
+    pub gas_limit: u64,
+}
+
+// Every callback has an empty default so an inspector only implements the hooks it cares about.
+pub trait Inspector {
+    // Called before the instruction at `evm.pc` is executed.
+    fn step(&mut self, _evm: &Evm<'_>, _opcode: u8) {}
+
+    fn frame_enter(&mut self, _frame: &Frame) {}
+
+    fn frame_exit(&mut self, _frame: &Frame, _result: &Result<ExitReason, String>, _gas_used: u64) {}
+
+    fn sload(&mut self, _address: U256, _key: U256, _value: U256) {}
+
+    fn sstore(&mut self, _address: U256, _key: U256, _old: U256, _new: U256) {}
+
+    fn log(&mut self, _log: &Log) {}
+}
+
+// Forwards every callback to each inspector in turn, so several can watch the same run.
+pub struct Inspectors<'a>(pub Vec<&'a mut dyn Inspector>);
+
+impl Inspector for Inspectors<'_> {
+    fn step(&mut self, evm: &Evm<'_>, opcode: u8) {
+        for inspector in self.0.iter_mut() {
+            inspector.step(evm, opcode);
+        }
+    }
+
+    fn frame_enter(&mut self, frame: &Frame) {
+        for inspector in self.0.iter_mut() {
+            inspector.frame_enter(frame);
+        }
+    }
+
+    fn frame_exit(&mut self, frame: &Frame, result: &Result<ExitReason, String>, gas_used: u64) {
+        for inspector in self.0.iter_mut() {
+            inspector.frame_exit(frame, result, gas_used);
+        }
+    }
+
+    fn sload(&mut self, address: U256, key: U256, value: U256) {
+        for inspector in self.0.iter_mut() {
+            inspector.sload(address, key, value);
+        }
+    }
+
+    fn sstore(&mut self, address: U256, key: U256, old: U256, new: U256) {
+        for inspector in self.0.iter_mut() {
+            inspector.sstore(address, key, old, new);
+        }
+    }
+
+    fn log(&mut self, log: &Log) {
+        for inspector in self.0.iter_mut() {
+            inspector.log(log);
+        }
+    }
+}
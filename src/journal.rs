@@ -0,0 +1,153 @@
+use primitive_types::U256;
+use crate::{Evm, opcodes};
+
+// No instruction reaches deeper into the stack than SWAP16.
+const MAX_STACK_REACH: usize = 17;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryWrite {
+    pub offset: usize,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageChange {
+    pub key: U256,
+    pub old: U256,
+    pub new: U256,
+}
+
+// Everything a single instruction changed, captured so that it can be undone.
+#[derive(Debug, Clone)]
+pub struct StepDelta {
+    pub pc: usize,
+    pub opcode: u8,
+    pub gas_used: u64,
+    pub refund: i64,
+    pub popped: Vec<U256>,
+    pub pushed: Vec<U256>,
+    pub memory: Option<MemoryWrite>,
+    pub memory_len: usize,
+    pub memory_words: usize,
+    pub storage: Option<StorageChange>,
+    pub logs: usize,
+    pub warmed_slot: Option<U256>,
+    pub warmed_address: Option<U256>,
+    stack_base: usize,
+    memory_before: Option<Vec<u8>>,
+}
+
+impl StepDelta {
+    // Records the state an instruction may touch, before it is executed.
+    pub fn capture(evm: &Evm<'_>) -> Self {
+        let opcode: u8 = *evm.code.get(evm.pc).unwrap_or(&0x00);
+        let stack_base: usize = evm.stack.len().saturating_sub(MAX_STACK_REACH);
+
+        let storage: Option<StorageChange> = match (opcode, evm.stack.last()) {
+            (0x55, Some(key)) => {
+                let old: U256 = *evm.storage.get(key).unwrap_or(&U256::zero());
+                Some(StorageChange { key: *key, old, new: old })
+            },
+            _ => None,
+        };
+
+        // accesses that turn a slot or address warm have to turn it cold again when undone
+        let warmed_slot: Option<U256> = match (opcode, evm.stack.last()) {
+            (0x54 | 0x55, Some(key)) if !evm.access.is_slot_warm(evm.address, *key) => Some(*key),
+            _ => None,
+        };
+        let warmed_address: Option<U256> = match (opcode, evm.stack.last()) {
+            (0x31 | 0x3b | 0x3c | 0x3f, Some(address)) => {
+                let address: U256 = *address & ((U256::one() << 160) - 1);
+                (!evm.access.is_address_warm(address)).then_some(address)
+            },
+            _ => None,
+        };
+
+        // only instructions that write into existing memory need a copy of it, growth alone is undone by truncating
+        let memory_before: Option<Vec<u8>> = opcodes::writes_memory(opcode).then(|| evm.memory.clone());
+
+        StepDelta {
+            pc: evm.pc,
+            opcode,
+            gas_used: evm.gas_used,
+            refund: evm.refund,
+            popped: evm.stack[stack_base..].to_vec(),
+            pushed: vec![],
+            memory: None,
+            memory_len: evm.memory.len(),
+            memory_words: evm.memory_words,
+            storage,
+            logs: evm.logs.len(),
+            warmed_slot,
+            warmed_address,
+            stack_base,
+            memory_before,
+        }
+    }
+
+    // Reduces the captured state to what the executed instruction actually changed.
+    pub fn complete(&mut self, evm: &Evm<'_>) {
+        let after: &[U256] = &evm.stack[self.stack_base.min(evm.stack.len())..];
+        let common: usize = self.popped.iter().zip(after.iter())
+            .take_while(|(before, after)| before == after)
+            .count();
+
+        self.pushed = after[common..].to_vec();
+        self.popped.drain(..common);
+        self.stack_base += common;
+
+        if let Some(before) = self.memory_before.take() {
+            let changed: Vec<usize> = (0..before.len().min(evm.memory.len()))
+                .filter(|i: &usize| before[*i] != evm.memory[*i])
+                .collect();
+
+            if let (Some(first), Some(last)) = (changed.first(), changed.last()) {
+                self.memory = Some(MemoryWrite {
+                    offset: *first,
+                    old: before[*first..=*last].to_vec(),
+                    new: evm.memory[*first..=*last].to_vec(),
+                });
+            }
+        }
+
+        if let Some(change) = self.storage.as_mut() {
+            change.new = *evm.storage.get(&change.key).unwrap_or(&U256::zero());
+        }
+    }
+
+    // Restores the state from before the instruction was executed.
+    pub fn revert(&self, evm: &mut Evm<'_>) {
+        evm.pc = self.pc;
+        evm.gas_used = self.gas_used;
+        evm.refund = self.refund;
+        evm.halted = false;
+
+        evm.stack.truncate(self.stack_base);
+        evm.stack.extend_from_slice(&self.popped);
+
+        if let Some(write) = &self.memory {
+            evm.memory[write.offset..write.offset + write.old.len()].copy_from_slice(&write.old);
+        }
+        evm.memory.truncate(self.memory_len);
+        evm.memory_words = self.memory_words;
+
+        if let Some(change) = &self.storage {
+            if change.old.is_zero() {
+                evm.storage.remove(&change.key);
+            } else {
+                evm.storage.insert(change.key, change.old);
+            }
+        }
+
+        evm.logs.truncate(self.logs);
+
+        if let Some(key) = self.warmed_slot {
+            evm.access.slots.remove(&(evm.address, key));
+        }
+        if let Some(address) = self.warmed_address {
+            evm.access.addresses.remove(&address);
+        }
+    }
+}
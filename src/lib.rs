@@ -3,19 +3,42 @@ use u256_literal::u256;
 use std::collections::HashMap;
 use sha3::{Digest, Keccak256};
 use chrono::{Utc, Datelike};
+use access::{AccessListItem, AccessSet};
+use inspector::{CallKind, Frame, Inspector};
+
+pub mod abi;
+pub mod access;
+pub mod artifact;
+pub mod debugger;
+pub mod estimate;
+pub mod gas;
+pub mod gas_report;
+pub mod inspector;
+pub mod journal;
+pub mod opcodes;
+pub mod repl;
+pub mod response;
+pub mod tracing;
+pub mod transaction;
 
 const STACK_UFLOW: &str = "Stack underflow";
 const MEM_OFLOW: &str = "Memory overflow";
-const MSG_SENDER: U256 = u256!(0xDEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEF);
-const CONTRACT_ADDRESS: U256 = u256!(0xADDDECAFADDDECAFADDDECAFADDDECAF);
+const STATIC_WRITE: &str = "State change in static context";
+pub const OUT_OF_GAS: &str = "Out of gas";
+pub const MSG_SENDER: U256 = u256!(0xDEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEF);
+pub const CONTRACT_ADDRESS: U256 = u256!(0xADDDECAFADDDECAFADDDECAFADDDECAF);
+const COINBASE: U256 = u256!(0xC014BA5EC014BA5EC014BA5EC014BA5EC014BA5E);
+pub const PRECOMPILES: u64 = 0x0a;
 const CHAIN_ID: U256 = u256!(0xBEEEEEF);
 const BASEFEE: U256 = u256!(1);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ContractAccount {
+    pub address: U256,
     pub code: Vec<u8>,
     pub storage: HashMap<U256, U256>,
     pub balance: U256,
+    pub nonce: u64,
 }
 
 pub struct Evm<'a> {
@@ -24,11 +47,41 @@ pub struct Evm<'a> {
     pub memory: Vec<u8>,
     pub memory_words: usize,
     pub storage: &'a mut HashMap<U256, U256>,
+    pub original_storage: HashMap<U256, U256>,
     pub code: &'a [u8],
     pub halted: bool,
     pub calldata: Vec<u8>,
     pub callvalue: U256,
     pub contract_balance: U256,
+    pub address: U256,
+    pub caller: U256,
+    pub origin: U256,
+    pub kind: CallKind,
+    pub block_number: u64,
+    pub is_static: bool,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub refund: i64,
+    pub access: AccessSet,
+    pub logs: Vec<Log>,
+    pub inspector: Option<&'a mut dyn Inspector>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TxEnv {
+    pub caller: U256,
+    pub gas_limit: u64,
+    pub access_list: Vec<AccessListItem>,
+    pub block_number: u64,
+    // a read-only call, where SSTORE and LOG fail as they would inside a STATICCALL
+    pub is_static: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Log {
+    pub address: U256,
+    pub topics: Vec<U256>,
+    pub data: Vec<u8>,
 }
 
 #[derive(Debug)]
@@ -41,506 +94,705 @@ pub enum ExitReason {
 impl ContractAccount {
     pub fn new(code: Vec<u8>) -> Self {
         ContractAccount {
-            code: code,
+            address: CONTRACT_ADDRESS,
+            code,
             storage: HashMap::new(),
             balance: U256::zero(),
+            nonce: 0,
+        }
+    }
+}
+
+impl Default for TxEnv {
+    fn default() -> Self {
+        TxEnv {
+            caller: MSG_SENDER,
+            gas_limit: gas::DEFAULT_GAS_LIMIT,
+            access_list: vec![],
+            block_number: u64::from(get_block_num()),
+            is_static: false,
         }
     }
 }
 
 impl<'a> Evm<'a> {
     pub fn new(account: &'a mut ContractAccount, callvalue: U256, calldata: Vec<u8>) -> Self {
+        Self::with_env(account, callvalue, calldata, &TxEnv::default())
+    }
+
+    pub fn with_env(account: &'a mut ContractAccount, callvalue: U256, calldata: Vec<u8>, env: &TxEnv) -> Self {
+        let mut access: AccessSet = AccessSet::default();
+        access.warm_address(env.caller);
+        access.warm_address(account.address);
+        access.warm_address(COINBASE);
+        for precompile in 1..=PRECOMPILES {
+            access.warm_address(U256::from(precompile));
+        }
+        access.apply(&env.access_list);
+
         Evm {
             pc: 0,   
             stack: vec![],
             memory: vec![],
             memory_words: 0,
             storage: &mut account.storage,
+            original_storage: HashMap::new(),
             code: &account.code,
             halted: false,
             calldata,
             callvalue,
             contract_balance: account.balance,
+            address: account.address,
+            caller: env.caller,
+            origin: env.caller,
+            kind: CallKind::Call,
+            block_number: env.block_number,
+            is_static: env.is_static,
+            gas_limit: env.gas_limit,
+            gas_used: 0,
+            refund: 0,
+            access,
+            logs: vec![],
+            inspector: None,
         }
     }
 
     pub fn run(&mut self) -> Result<ExitReason, String> {
-        while !self.halted {
-            let opcode: u8 = self.code[self.pc];
-            self.pc += 1;
-
-            match opcode {
-                0x00 => return Ok(ExitReason::Stop), // Stop
-
-                0x01 => { // Add
-                    let (a, b) = Self::pop_two(self)?;
-                    self.stack.push(a + b);
-                },
-
-                0x02 => { // Multiply
-                    let (a, b) = Self::pop_two(self)?;
-                    self.stack.push(a * b);
-                },
-
-                0x03 => { // Subtract
-                    let (a, b) = Self::pop_two(self)?;
-                    self.stack.push(a.overflowing_sub(b).0);
-                },
-
-                0x04 => { // Divide
-                    let (a, b) = Self::pop_two(self)?;
-                    if b.is_zero() {
-                        self.stack.push(U256::zero());
-                    } else {
-                        self.stack.push(a / b);
-                    }
-                },
-
-                0x05 => { // Signed Divide
-                    let (a, b) = Self::pop_two(self)?;
-                    if a.is_zero() {
-                        self.stack.push(U256::zero());
-                    }
+        let frame: Frame = self.enter();
+        let result: Result<ExitReason, String> = self.interpret();
+        self.exit(&frame, result)
+    }
 
-                    let sa: i128 = Self::u256_to_i128(a);
-                    let sb: i128 = Self::u256_to_i128(b);
+    // `run` split in two for callers that step through execution themselves: `enter` before the first
+    // step, `exit` with however execution ended
+    pub fn enter(&mut self) -> Frame {
+        let frame: Frame = self.frame();
+        self.inspect(|inspector, _| inspector.frame_enter(&frame));
+        frame
+    }
 
-                    if sb == i128::MIN && sa == -1 {
-                        self.stack.push(b);
-                    } else {
-                        let result: i128 = sb / sa;
-                        self.stack.push(U256::from(result as i128));
-                    }
-                },
-
-                0x06 => { // MOD
-                    let (a, b) = Self::pop_two(self)?;
-                    self.stack.push(a % b);
-                },
-
-                0x08 => { // ADDMOD
-                    let a: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let b: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let n: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    if n.is_zero() {
-                        self.stack.push(U256::zero());
-                    } else {
-                        let sum: U256 = a.overflowing_add(b).0;
-                        self.stack.push(sum % n);
-                    }
-                },
-
-                0x09 => { // MULMOD
-                    let a: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let b: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let n: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    if n.is_zero() {
-                        self.stack.push(U256::zero());
-                    } else {
-                        let prod: U512 = a.full_mul(b);
-                        let n512: U512 = U512::from(n);
-                        let result: U512 = prod % n512;
-                        self.stack.push(Self::u512_to_u256(result));
-                    }
-                },
+    pub fn exit(&mut self, frame: &Frame, result: Result<ExitReason, String>) -> Result<ExitReason, String> {
+        if result.is_err() {
+            self.gas_used = self.gas_limit;
+        }
+        if !matches!(result, Ok(ExitReason::Return(_) | ExitReason::Stop)) {
+            self.refund = 0;
+        }
 
-                0x0A => { // Exponent
-                    let (a, b) = Self::pop_two(self)?;
-                    self.stack.push(a.pow(b));
-                },
+        let gas_used: u64 = self.gas_used;
+        self.inspect(|inspector, _| inspector.frame_exit(frame, &result, gas_used));
+        result
+    }
 
-                0x10 => { // LT
-                    let (a, b) = Self::pop_two(self)?;
-                    self.stack.push(if a < b { U256::one() } else { U256::zero() });
-                },
+    pub fn frame(&self) -> Frame {
+        Frame {
+            kind: self.kind,
+            caller: self.caller,
+            address: self.address,
+            value: self.callvalue,
+            input: self.calldata.clone(),
+            gas_limit: self.gas_limit,
+        }
+    }
 
-                0x11 => { // GT   
-                    let (a, b) = Self::pop_two(self)?;
-                    self.stack.push(if a > b { U256::one() } else { U256::zero() });
-                },
+    fn interpret(&mut self) -> Result<ExitReason, String> {
+        while !self.halted {
+            if let Some(exit) = self.step()? {
+                return Ok(exit);
+            }
+        }
+        Ok(ExitReason::Stop)
+    }
 
-                0x12 => { // SLT
-                    let (a, b) = Self::pop_two(self)?;
+    // Executes the instruction at `pc`. Returns the exit reason once execution has halted.
+    pub fn step(&mut self) -> Result<Option<ExitReason>, String> {
+        if self.halted {
+            return Err("Execution has already halted".to_string());
+        }
 
-                    let a: i128 = Self::u256_to_i128(a);
-                    let b: i128 = Self::u256_to_i128(b);
+        let result: Result<Option<ExitReason>, String> = self.execute();
+        if !matches!(result, Ok(None)) {
+            self.halted = true;
+        }
+        result
+    }
 
-                    self.stack.push(if a < b { U256::one() } else { U256::zero() });
-                },
+    fn execute(&mut self) -> Result<Option<ExitReason>, String> {
+        if self.pc >= self.code.len() {
+            return Ok(Some(ExitReason::Stop));
+        }
 
-                0x13 => { // SGT
-                    let (a, b) = Self::pop_two(self)?;
+        let opcode: u8 = self.code[self.pc];
+        self.inspect(|inspector, evm| inspector.step(evm, opcode));
+        self.pc += 1;
 
-                    let a: i128 = Self::u256_to_i128(a);
-                    let b: i128 = Self::u256_to_i128(b);
+        self.charge_gas(gas::static_cost(opcode))?;
 
-                    self.stack.push(if a > b { U256::one() } else { U256::zero() });
-                },
+        match opcode {
+            0x00 => return Ok(Some(ExitReason::Stop)), // Stop
 
-                0x14 => { // EQ
-                    let (a, b) = Self::pop_two(self)?;
-                    self.stack.push(if a == b { U256::one() } else { U256::zero() });
-                },
+            0x01 => { // Add
+                let (a, b) = Self::pop_two(self)?;
+                self.stack.push(a + b);
+            },
 
-                0x15 => { // ISZERO
-                    let a: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    self.stack.push(if a.is_zero() { U256::one() } else { U256::zero() });
-                },
+            0x02 => { // Multiply
+                let (a, b) = Self::pop_two(self)?;
+                self.stack.push(a * b);
+            },
 
-                0x16 => { // AND
-                    let (a, b) = Self::pop_two(self)?;
-                    self.stack.push(a & b);
-                },
+            0x03 => { // Subtract
+                let (a, b) = Self::pop_two(self)?;
+                self.stack.push(a.overflowing_sub(b).0);
+            },
 
-                0x17 => { // OR
-                    let (a, b) = Self::pop_two(self)?;
-                    self.stack.push(a | b);
-                },
-                
-                0x18 => { // XOR
-                    let (a, b) = Self::pop_two(self)?;
-                    self.stack.push(a ^ b);
-                },
-
-                0x19 => { // NOT
-                    let a: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    self.stack.push(!a);
-                },
-
-                0x1A => { // BYTE
-                    let (i, x) = Self::pop_two(self)?;
-    
-                    let index: usize = i.as_usize();
-                    if index >= 32 {
-                        self.stack.push(U256::zero());
+            0x04 => { // Divide
+                let (a, b) = Self::pop_two(self)?;
+                if b.is_zero() {
+                    self.stack.push(U256::zero());
+                } else {
+                    self.stack.push(a / b);
+                }
+            },
+
+            0x05 => { // Signed Divide
+                let (a, b) = Self::pop_two(self)?;
+                if a.is_zero() {
+                    self.stack.push(U256::zero());
+                }
+
+                let sa: i128 = Self::u256_to_i128(a);
+                let sb: i128 = Self::u256_to_i128(b);
+
+                if sb == i128::MIN && sa == -1 {
+                    self.stack.push(b);
+                } else {
+                    let result: i128 = sb / sa;
+                    self.stack.push(U256::from(result));
+                }
+            },
+
+            0x06 => { // MOD
+                let (a, b) = Self::pop_two(self)?;
+                self.stack.push(a % b);
+            },
+
+            0x08 => { // ADDMOD
+                let a: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let b: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let n: U256 = self.stack.pop().expect(STACK_UFLOW);
+                if n.is_zero() {
+                    self.stack.push(U256::zero());
+                } else {
+                    let sum: U256 = a.overflowing_add(b).0;
+                    self.stack.push(sum % n);
+                }
+            },
+
+            0x09 => { // MULMOD
+                let a: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let b: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let n: U256 = self.stack.pop().expect(STACK_UFLOW);
+                if n.is_zero() {
+                    self.stack.push(U256::zero());
+                } else {
+                    let prod: U512 = a.full_mul(b);
+                    let n512: U512 = U512::from(n);
+                    let result: U512 = prod % n512;
+                    self.stack.push(Self::u512_to_u256(result));
+                }
+            },
+
+            0x0A => { // Exponent
+                let (a, b) = Self::pop_two(self)?;
+                self.charge_gas(gas::exp_cost(b))?;
+                self.stack.push(a.pow(b));
+            },
+
+            0x10 => { // LT
+                let (a, b) = Self::pop_two(self)?;
+                self.stack.push(if a < b { U256::one() } else { U256::zero() });
+            },
+
+            0x11 => { // GT   
+                let (a, b) = Self::pop_two(self)?;
+                self.stack.push(if a > b { U256::one() } else { U256::zero() });
+            },
+
+            0x12 => { // SLT
+                let (a, b) = Self::pop_two(self)?;
+
+                let a: i128 = Self::u256_to_i128(a);
+                let b: i128 = Self::u256_to_i128(b);
+
+                self.stack.push(if a < b { U256::one() } else { U256::zero() });
+            },
+
+            0x13 => { // SGT
+                let (a, b) = Self::pop_two(self)?;
+
+                let a: i128 = Self::u256_to_i128(a);
+                let b: i128 = Self::u256_to_i128(b);
+
+                self.stack.push(if a > b { U256::one() } else { U256::zero() });
+            },
+
+            0x14 => { // EQ
+                let (a, b) = Self::pop_two(self)?;
+                self.stack.push(if a == b { U256::one() } else { U256::zero() });
+            },
+
+            0x15 => { // ISZERO
+                let a: U256 = self.stack.pop().expect(STACK_UFLOW);
+                self.stack.push(if a.is_zero() { U256::one() } else { U256::zero() });
+            },
+
+            0x16 => { // AND
+                let (a, b) = Self::pop_two(self)?;
+                self.stack.push(a & b);
+            },
+
+            0x17 => { // OR
+                let (a, b) = Self::pop_two(self)?;
+                self.stack.push(a | b);
+            },
+            
+            0x18 => { // XOR
+                let (a, b) = Self::pop_two(self)?;
+                self.stack.push(a ^ b);
+            },
+
+            0x19 => { // NOT
+                let a: U256 = self.stack.pop().expect(STACK_UFLOW);
+                self.stack.push(!a);
+            },
+
+            0x1A => { // BYTE
+                let (i, x) = Self::pop_two(self)?;
+
+                let index: usize = i.as_usize();
+                if index >= 32 {
+                    self.stack.push(U256::zero());
+                } else {
+                    let shift: usize = 8 * (31 - index);
+                    let byte: U256 = (x >> shift) & U256::from(0xFF);
+                    self.stack.push(byte);
+                }
+            },
+
+            0x1B => { // SHL
+                let (shift, value) = Self::pop_two(self)?;
+                self.stack.push(value << shift);
+            },
+
+            0x1C => { // SHR
+                let (shift, value) = Self::pop_two(self)?;
+                self.stack.push(value >> shift);
+            },
+
+            0x20 => { // KECCAK256
+                let (offset, size) = Self::pop_two(self)?;
+                let hash: U256 = Self::evm_keccak256(self, offset, size)?;
+                self.stack.push(hash);
+            },
+
+            0x30 => { // ADDRESS
+                self.stack.push(self.address);
+            },
+
+            0x31 => { // BALANCE
+                let address: U256 = Self::to_address(self.stack.pop().expect(STACK_UFLOW));
+                self.access_account(address)?;
+
+                let balance: U256 = if address == self.address { self.contract_balance } else { U256::zero() };
+                self.stack.push(balance);
+            },
+
+            0x32 => { // ORIGIN
+                self.stack.push(self.origin);
+            },
+
+            0x33 => { // CALLER
+                self.stack.push(self.caller);
+            },
+
+            0x34 => { // CALLVALUE
+                self.stack.push(self.callvalue);
+            },
+
+            0x35 => { // CALLDATALOAD
+                let offset: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let offset: usize = Self::u256_to_usize(offset)?;
+                let mut buf: [u8; 32] = [0u8; 32];
+
+                for (j, byte) in buf.iter_mut().enumerate() {
+                    *byte = *self.calldata.get(offset + j).unwrap_or(&0);
+                }
+
+                let value: U256 = U256::from_big_endian(&buf);
+                self.stack.push(value);
+            },
+
+            0x36 => { // CALLDATASIZE
+                self.stack.push(U256::from(self.calldata.len()));
+            },
+
+            0x37 => { // CALLDATACOPY
+                let dest_offset: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let offset: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let size: U256 = self.stack.pop().expect(STACK_UFLOW);
+
+                let dest_offset: usize = Self::u256_to_usize(dest_offset)?;
+                let offset: usize = Self::u256_to_usize(offset)?;
+                let size: usize = Self::u256_to_usize(size)?;
+
+                self.check_memory_length(dest_offset, size)?;
+                self.charge_gas(gas::copy_cost(size))?;
+
+                for i in 0..size {
+                    self.memory[dest_offset + i] = if offset + i < self.calldata.len() {
+                        self.calldata[offset + i]
                     } else {
-                        let shift: usize = 8 * (31 - index);
-                        let byte: U256 = (x >> shift) & U256::from(0xFF);
-                        self.stack.push(byte);
-                    }
-                },
-
-                0x1B => { // SHL
-                    let (shift, value) = Self::pop_two(self)?;
-                    self.stack.push(value << shift);
-                },
-
-                0x1C => { // SHR
-                    let (shift, value) = Self::pop_two(self)?;
-                    self.stack.push(value >> shift);
-                },
-
-                0x20 => { // KECCAK256
-                    let (offset, size) = Self::pop_two(self)?;
-                    let hash: U256 = Self::evm_keccak256(self, offset, size)?;
-                    self.stack.push(hash);
-                },
-
-                0x30 => { // ADDRESS
-                    self.stack.push(CONTRACT_ADDRESS);
-                },
-
-                0x32 => { // ORIGIN
-                    self.stack.push(MSG_SENDER);
-                },
-
-                0x33 => { // CALLER
-                    self.stack.push(MSG_SENDER);
-                },
-
-                0x34 => { // CALLVALUE
-                    self.stack.push(self.callvalue);
-                },
-
-                0x35 => { // CALLDATALOAD
-                    let offset: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let offset: usize = Self::u256_to_usize(offset)?;
-                    let mut buf: [u8; 32] = [0u8; 32];
-
-                    for j in 0..32 {
-                        if offset + j < self.calldata.len() {
-                            buf[j] = self.calldata[offset + j];
-                        } else {
-                            buf[j] = 0;
-                        }
-                    }
-
-                    let value: U256 = U256::from_big_endian(&buf);
-                    self.stack.push(value);
-                },
-
-                0x36 => { // CALLDATASIZE
-                    self.stack.push(U256::from(self.calldata.len()));
-                },
-
-                0x37 => { // CALLDATACOPY
-                    let dest_offset: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let offset: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let size: U256 = self.stack.pop().expect(STACK_UFLOW);
-
-                    let dest_offset: usize = Self::u256_to_usize(dest_offset)?;
-                    let offset: usize = Self::u256_to_usize(offset)?;
-                    let size: usize = Self::u256_to_usize(size)?;
-
-                    self.check_memory_length(dest_offset + size);
-
-                    for i in 0..size {
-                        self.memory[dest_offset + i] = if offset + i < self.calldata.len() {
-                            self.calldata[offset + i]
-                        } else {
-                            0u8
-                        }
+                        0u8
                     }
-                },
+                }
+            },
 
-                0x39 => { // CODECOPY
-                    let dest_offset: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let offset: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let size: U256 = self.stack.pop().expect(STACK_UFLOW);
+            0x39 => { // CODECOPY
+                let dest_offset: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let offset: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let size: U256 = self.stack.pop().expect(STACK_UFLOW);
 
-                    let dest_offset: usize = Self::u256_to_usize(dest_offset)?;
-                    let offset: usize = Self::u256_to_usize(offset)?;
-                    let size: usize = Self::u256_to_usize(size)?;
+                let dest_offset: usize = Self::u256_to_usize(dest_offset)?;
+                let offset: usize = Self::u256_to_usize(offset)?;
+                let size: usize = Self::u256_to_usize(size)?;
 
-                    self.check_memory_length(dest_offset + size);
+                self.check_memory_length(dest_offset, size)?;
+                self.charge_gas(gas::copy_cost(size))?;
 
-                    for i in 0..size {
-                        self.memory[dest_offset + i] = if offset + i < self.code.len() {
-                            self.code[offset + i]
-                        } else {
-                            0u8
-                        }
+                for i in 0..size {
+                    self.memory[dest_offset + i] = if offset + i < self.code.len() {
+                        self.code[offset + i]
+                    } else {
+                        0u8
                     }
-                },
-
-                0x43 => { // BLOCK NUMBER
-                    self.stack.push(U256::from(get_block_num()));
-                },
-
-                0x46 => { // CHAINID
-                    self.stack.push(CHAIN_ID);
-                },
-
-                0x47 => { // SELFBALANCE
-                    self.stack.push(self.contract_balance);
-                },
-
-                0x48 => { // BASEFEE
-                    self.stack.push(BASEFEE);
-                },
-
-                0x50 => { // POP
-                    self.stack.pop().expect(STACK_UFLOW);
-                },
-
-                0x51 => { // MLOAD
-                    let offset_u256: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let offset: usize = Self::u256_to_usize(offset_u256)?;
+                }
+            },
+
+            0x3b => { // EXTCODESIZE
+                let address: U256 = Self::to_address(self.stack.pop().expect(STACK_UFLOW));
+                self.access_account(address)?;
+
+                let size: usize = if address == self.address { self.code.len() } else { 0 };
+                self.stack.push(U256::from(size));
+            },
+
+            0x3c => { // EXTCODECOPY
+                let address: U256 = Self::to_address(self.stack.pop().expect(STACK_UFLOW));
+                let dest_offset: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let offset: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let size: U256 = self.stack.pop().expect(STACK_UFLOW);
+
+                let dest_offset: usize = Self::u256_to_usize(dest_offset)?;
+                let offset: usize = Self::u256_to_usize(offset)?;
+                let size: usize = Self::u256_to_usize(size)?;
+
+                self.access_account(address)?;
+                self.check_memory_length(dest_offset, size)?;
+                self.charge_gas(gas::copy_cost(size))?;
+
+                let code: &[u8] = if address == self.address { self.code } else { &[] };
+                for i in 0..size {
+                    self.memory[dest_offset + i] = *code.get(offset + i).unwrap_or(&0u8);
+                }
+            },
+
+            0x3f => { // EXTCODEHASH
+                let address: U256 = Self::to_address(self.stack.pop().expect(STACK_UFLOW));
+                self.access_account(address)?;
+
+                if address == self.address {
+                    let hash = Keccak256::digest(self.code);
+                    self.stack.push(U256::from_big_endian(&hash));
+                } else {
+                    self.stack.push(U256::zero());
+                }
+            },
 
-                    let new_size_bytes: usize = offset.checked_add(32).expect("Error calculating new memory size in bytes");
-                    let new_size_words: usize = (new_size_bytes + 31) / 32;
-                    self.memory_words = self.memory_words.max(new_size_words);
+            0x41 => { // COINBASE
+                self.stack.push(COINBASE);
+            },
 
-                    let mut buf: [u8; 32] = [0u8; 32];
-                    for i in 0..32 {
-                        buf[i] = *self.memory.get(offset + i).unwrap_or(&0);
-                    }
-                    
-                    let value: U256 = U256::from_big_endian(&buf);
-                    self.stack.push(value);
-                },
+            0x43 => { // BLOCK NUMBER
+                self.stack.push(U256::from(self.block_number));
+            },
 
-                0x52 => { // MSTORE
-                    let offset_u256: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let value: U256 = self.stack.pop().expect(STACK_UFLOW);
+            0x46 => { // CHAINID
+                self.stack.push(CHAIN_ID);
+            },
 
-                    let offset: usize = Self::u256_to_usize(offset_u256)?;
-                    let end: usize = offset.checked_add(32).expect(MEM_OFLOW);
-                    let new_words: usize = (end + 31) / 32;
+            0x47 => { // SELFBALANCE
+                self.stack.push(self.contract_balance);
+            },
 
-                    self.memory_words = self.memory_words.max(new_words);
+            0x48 => { // BASEFEE
+                self.stack.push(BASEFEE);
+            },
 
-                    self.check_memory_length(end);
+            0x50 => { // POP
+                self.stack.pop().expect(STACK_UFLOW);
+            },
 
-                    let mut buf: [u8; 32] = [0u8; 32];
-                    value.to_big_endian(&mut buf);
+            0x51 => { // MLOAD
+                let offset_u256: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let offset: usize = Self::u256_to_usize(offset_u256)?;
 
-                    for i in 0..32 {
-                        self.memory[offset + i] = buf[i];
-                    }
-                },
-
-                0x53 => { // MSTORE8
-                    
-                    let offset_u256: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let value: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    
-                    let offset: usize = Self::u256_to_usize(offset_u256)?;
-                    
-                    let end: usize = offset.checked_add(1).expect(MEM_OFLOW);
-                    let new_words: usize = (end + 31) / 32;
-                    self.memory_words = self.memory_words.max(new_words);
-
-                    self.check_memory_length(end);
-
-                    self.memory[offset] = value.low_u32() as u8;
-                },
-
-                0x54 => { // SLOAD
-                    let key: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let value: U256 = *self.storage.get(&key).unwrap_or(&U256::zero());
-                    self.stack.push(value);
-                },
-
-                0x55 => { // SSTORE    
-                    let key: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let value: U256 = self.stack.pop().expect(STACK_UFLOW);
-
-                    if value.is_zero() {
-                        self.storage.remove(&key);
-                    } else {
-                        self.storage.insert(key, value);
-                    };
-                },
-
-                0x56 => { // JUMP
-                    let counter: usize = Self::u256_to_usize(self.stack.pop().expect(STACK_UFLOW))?;
-                    if !self.valid_jumpdest(counter) {
-                        return Ok(ExitReason::Revert(vec![0x56]));
-                    }
-                    self.pc = counter;
-                },
-
-                0x57 => { // JUMPI
-                    let counter_dest: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let condition: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    
-                    if condition != U256::zero() {
-                        if !self.valid_jumpdest(Self::u256_to_usize(counter_dest)?) {
-                            return Ok(ExitReason::Revert(vec![0x57]));
-                        }
-                        self.pc = Self::u256_to_usize(counter_dest)?
-                    };
-                },
-
-                0x58 => { // PC
-                    self.stack.push(U256::from(self.pc - 1));
-                },
-
-                0x5b => { // JUMPDEST
-                    // nothing
-                },
-
-                0x5e => { // MCOPY
-                    let dest_offset: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let offset: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let size: U256 = self.stack.pop().expect(STACK_UFLOW);
-
-                    let dest_offset: usize = Self::u256_to_usize(dest_offset)?;
-                    let offset: usize = Self::u256_to_usize(offset)?;
-                    let size: usize = Self::u256_to_usize(size)?;
-
-                    let end: usize = dest_offset.max(offset).checked_add(size).ok_or(MEM_OFLOW)?;
-
-                    self.check_memory_length(end);
-
-                    let mut tmp: Vec<u8> = vec![0u8; size];
-
-                    for i in 0..size {
-                        tmp[i] = if offset + i < self.memory.len() {
-                            self.memory[offset + i]
-                        } else {
-                            0
-                        };
-                    }
+                self.check_memory_length(offset, 32)?;
 
-                    for i in 0..size {
-                        self.memory[dest_offset + i] = tmp[i];
-                    }
-                },
-
-                0x5f => { // PUSH0
-                    self.stack.push(U256::zero());
-                },
+                let mut buf: [u8; 32] = [0u8; 32];
+                for (i, byte) in buf.iter_mut().enumerate() {
+                    *byte = *self.memory.get(offset + i).unwrap_or(&0);
+                }
+                
+                let value: U256 = U256::from_big_endian(&buf);
+                self.stack.push(value);
+            },
 
-                0x60..=0x7f => { // PUSHn
-                    let n: u8 = opcode - 0x5f;
-                    let n: usize = n as usize;
+            0x52 => { // MSTORE
+                let offset_u256: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let value: U256 = self.stack.pop().expect(STACK_UFLOW);
 
-                    if self.pc + n > self.code.len() {
-                        return Err("Not enough bytes for PUSH".to_string());
-                    }
+                let offset: usize = Self::u256_to_usize(offset_u256)?;
 
-                    let data: &[u8] = &self.code[self.pc..self.pc + n];
-                    self.pc += n;
+                self.check_memory_length(offset, 32)?;
 
-                    let mut buf = [0u8; 32];
-                    buf[32 - data.len()..].copy_from_slice(data);
+                let mut buf: [u8; 32] = [0u8; 32];
+                value.to_big_endian(&mut buf);
 
-                    self.stack.push(U256::from_big_endian(&buf));
-                },
+                self.memory[offset..offset + 32].copy_from_slice(&buf);
+            },
 
-                0x80..=0x8f => { // DUPn
-                    let n: u8 = opcode - 0x7f;
-                    let n: usize = n as usize;
-
-                    if self.stack.len() < n {
-                        return Err(STACK_UFLOW.to_string());
+            0x53 => { // MSTORE8
+                
+                let offset_u256: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let value: U256 = self.stack.pop().expect(STACK_UFLOW);
+                
+                let offset: usize = Self::u256_to_usize(offset_u256)?;
+
+                self.check_memory_length(offset, 1)?;
+
+                self.memory[offset] = value.low_u32() as u8;
+            },
+
+            0x54 => { // SLOAD
+                let key: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let cold: bool = self.access.warm_slot(self.address, key);
+                self.charge_gas(gas::sload_cost(cold))?;
+
+                let value: U256 = *self.storage.get(&key).unwrap_or(&U256::zero());
+                let address: U256 = self.address;
+                self.inspect(|inspector, _| inspector.sload(address, key, value));
+                self.stack.push(value);
+            },
+
+            0x55 => { // SSTORE    
+                if self.is_static {
+                    return Err(STATIC_WRITE.to_string());
+                }
+                if self.gas_remaining() <= gas::SSTORE_SENTRY {
+                    return Err(OUT_OF_GAS.to_string());
+                }
+
+                let key: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let value: U256 = self.stack.pop().expect(STACK_UFLOW);
+
+                let old: U256 = *self.storage.get(&key).unwrap_or(&U256::zero());
+                let original: U256 = *self.original_storage.entry(key).or_insert(old);
+
+                let cold: bool = self.access.warm_slot(self.address, key);
+                let (cost, refund) = gas::sstore_cost(original, old, value, cold);
+                self.charge_gas(cost)?;
+                self.refund += refund;
+
+                let address: U256 = self.address;
+                self.inspect(|inspector, _| inspector.sstore(address, key, old, value));
+
+                if value.is_zero() {
+                    self.storage.remove(&key);
+                } else {
+                    self.storage.insert(key, value);
+                };
+            },
+
+            0x56 => { // JUMP
+                let counter: usize = Self::u256_to_usize(self.stack.pop().expect(STACK_UFLOW))?;
+                if !self.valid_jumpdest(counter) {
+                    return Ok(Some(ExitReason::Revert(vec![0x56])));
+                }
+                self.pc = counter;
+            },
+
+            0x57 => { // JUMPI
+                let counter_dest: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let condition: U256 = self.stack.pop().expect(STACK_UFLOW);
+                
+                if condition != U256::zero() {
+                    if !self.valid_jumpdest(Self::u256_to_usize(counter_dest)?) {
+                        return Ok(Some(ExitReason::Revert(vec![0x57])));
                     }
+                    self.pc = Self::u256_to_usize(counter_dest)?
+                };
+            },
+
+            0x58 => { // PC
+                self.stack.push(U256::from(self.pc - 1));
+            },
+
+            0x5b => { // JUMPDEST
+                // nothing
+            },
+
+            0x5e => { // MCOPY
+                let dest_offset: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let offset: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let size: U256 = self.stack.pop().expect(STACK_UFLOW);
+
+                let dest_offset: usize = Self::u256_to_usize(dest_offset)?;
+                let offset: usize = Self::u256_to_usize(offset)?;
+                let size: usize = Self::u256_to_usize(size)?;
+
+                self.check_memory_length(dest_offset.max(offset), size)?;
+                self.charge_gas(gas::copy_cost(size))?;
+
+                let mut tmp: Vec<u8> = vec![0u8; size];
+
+                for (i, byte) in tmp.iter_mut().enumerate() {
+                    *byte = *self.memory.get(offset + i).unwrap_or(&0);
+                }
+
+                self.memory[dest_offset..dest_offset + size].copy_from_slice(&tmp);
+            },
+
+            0x5f => { // PUSH0
+                self.stack.push(U256::zero());
+            },
+
+            0x60..=0x7f => { // PUSHn
+                let n: u8 = opcode - 0x5f;
+                let n: usize = n as usize;
+
+                if self.pc + n > self.code.len() {
+                    return Err("Not enough bytes for PUSH".to_string());
+                }
+
+                let data: &[u8] = &self.code[self.pc..self.pc + n];
+                self.pc += n;
+
+                let mut buf = [0u8; 32];
+                buf[32 - data.len()..].copy_from_slice(data);
+
+                self.stack.push(U256::from_big_endian(&buf));
+            },
+
+            0x80..=0x8f => { // DUPn
+                let n: u8 = opcode - 0x7f;
+                let n: usize = n as usize;
+
+                if self.stack.len() < n {
+                    return Err(STACK_UFLOW.to_string());
+                }
+
+                let value = self.stack[self.stack.len() - n];
+                self.stack.push(value);
+            },
+
+            0x90..=0x9f => { // SWAPn
+                let n: usize = (opcode - 0x8f) as usize;
+                let len = self.stack.len();
+
+                if len <= n {
+                    return Err(STACK_UFLOW.to_string());
+                }
+
+                self.stack.swap(len - 1, len - 1 - n);
+            },
+                            
+            0xa0..=0xa4 => { // LOGn
+                if self.is_static {
+                    return Err(STATIC_WRITE.to_string());
+                }
+                let n: usize = (opcode - 0xa0) as usize;
+                let (offset, size) = Self::pop_two(self)?;
+
+                let mut topics: Vec<U256> = Vec::with_capacity(n);
+                for _ in 0..n {
+                    topics.push(self.stack.pop().expect(STACK_UFLOW));
+                }
+
+                let offset: usize = Self::u256_to_usize(offset)?;
+                let size: usize = Self::u256_to_usize(size)?;
+                self.check_memory_length(offset, size)?;
+                self.charge_gas(gas::log_cost(n, size))?;
+
+                let log: Log = Log {
+                    address: self.address,
+                    topics,
+                    data: self.read_memory(offset, size),
+                };
+                self.inspect(|inspector, _| inspector.log(&log));
+                self.logs.push(log);
+            },
+
+            0xf3 => { // RETURN
+                
+                let offset_u256: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let size_u256: U256   = self.stack.pop().expect(STACK_UFLOW);
 
-                    let value = self.stack[self.stack.len() - n];
-                    self.stack.push(value);
-                },
-
-                0x90..=0x9f => { // SWAPn
-                    let n: usize = (opcode - 0x8f) as usize;
-                    let len = self.stack.len();
-
-                    if len <= n {
-                        return Err(STACK_UFLOW.to_string());
-                    }
+                let offset: usize = Self::u256_to_usize(offset_u256)?;
+                let size: usize   = Self::u256_to_usize(size_u256)?;
 
-                    self.stack.swap(len - 1, len - 1 - n);
-                },
-                                
-                0xf3 => { // RETURN
-                    
-                    let offset_u256: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let size_u256: U256   = self.stack.pop().expect(STACK_UFLOW);
+                self.check_memory_length(offset, size)?;
 
-                    let offset: usize = Self::u256_to_usize(offset_u256)?;
-                    let size: usize   = Self::u256_to_usize(size_u256)?;
+                let data: Vec<u8> = self.read_memory(offset, size);
+                return Ok(Some(ExitReason::Return(data)));
+            },
 
-                    let end: usize = offset.checked_add(size).ok_or(MEM_OFLOW)?;
+            0xfd => { // REVERT
+                let offset: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let size: U256 = self.stack.pop().expect(STACK_UFLOW);
+                let offset: usize = Self::u256_to_usize(offset)?;
+                let size: usize = Self::u256_to_usize(size)?;
+                
+                self.check_memory_length(offset, size)?;
 
-                    self.check_memory_length(end);
+                let data: Vec<u8> = self.read_memory(offset, size);
+                return Ok(Some(ExitReason::Revert(data)));
+            },
 
-                    let data: Vec<u8> = self.memory[offset..end].to_vec();
-                    return Ok(ExitReason::Return(data));
-                },
+            0xfe => { // INVALID
+                return Ok(Some(ExitReason::Revert(vec![0xfe])));
+            },
 
-                0xfd => { // REVERT
-                    let offset: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let size: U256 = self.stack.pop().expect(STACK_UFLOW);
-                    let offset: usize = Self::u256_to_usize(offset)?;
-                    let size: usize = Self::u256_to_usize(size)?;
-                    
-                    let end: usize = offset.checked_add(size).ok_or(MEM_OFLOW)?;
-                    self.check_memory_length(end);
+            _ => return Err(format!("Unknown opcode: {:#x}", opcode)),
 
-                    let data: Vec<u8> = self.memory[offset..end].to_vec();
-                    return Ok(ExitReason::Revert(data));
-                },
+        }
+        Ok(None)
+    }
 
-                0xfe => { // INVALID
-                    return Ok(ExitReason::Revert(vec![0xfe]));
-                },
+    fn inspect<F: FnOnce(&mut dyn Inspector, &Self)>(&mut self, f: F) {
+        if let Some(inspector) = self.inspector.take() {
+            f(&mut *inspector, self);
+            self.inspector = Some(inspector);
+        }
+    }
 
-                _ => return Err(format!("Unknown opcode: {:#x}", opcode)),
+    fn access_account(&mut self, address: U256) -> Result<(), String> {
+        let cold: bool = self.access.warm_address(address);
+        self.charge_gas(gas::account_access_cost(cold))
+    }
 
-            }
-        }
-        Ok(ExitReason::Stop)
+    fn to_address(x: U256) -> U256 {
+        x & ((U256::one() << 160) - 1)
     }
 
     fn pop_two(&mut self) -> Result<(U256, U256), String>  {
@@ -568,11 +820,10 @@ impl<'a> Evm<'a> {
         let offset: usize = Self::u256_to_usize(offset)?;
         let size: usize = Self::u256_to_usize(size)?;
 
-        let end: usize = offset.checked_add(size).ok_or(MEM_OFLOW)?;
-
-        self.check_memory_length(end);
+        self.check_memory_length(offset, size)?;
+        self.charge_gas(gas::keccak256_cost(size))?;
 
-        let data: &[u8] = &self.memory[offset..end];
+        let data: Vec<u8> = self.read_memory(offset, size);
 
         let mut hasher = Keccak256::new();
         hasher.update(data);
@@ -589,10 +840,49 @@ impl<'a> Evm<'a> {
         }
     }
 
-    fn check_memory_length(&mut self, end: usize) {
+    fn check_memory_length(&mut self, offset: usize, size: usize) -> Result<(), String> {
+        if size == 0 {
+            return Ok(());
+        }
+
+        let end: usize = offset.checked_add(size).ok_or(MEM_OFLOW)?;
+        let words: usize = end.div_ceil(32);
+
+        if words > self.memory_words {
+            let cost: u64 = gas::memory_cost(words) - gas::memory_cost(self.memory_words);
+            self.charge_gas(cost)?;
+            self.memory_words = words;
+        }
+
         if self.memory.len() < end {
             self.memory.resize(end, 0u8);
         }
+        Ok(())
+    }
+
+    fn read_memory(&self, offset: usize, size: usize) -> Vec<u8> {
+        if size == 0 {
+            return vec![];
+        }
+        self.memory[offset..offset + size].to_vec()
+    }
+
+    fn charge_gas(&mut self, amount: u64) -> Result<(), String> {
+        let used: u64 = self.gas_used.saturating_add(amount);
+        if used > self.gas_limit {
+            self.gas_used = self.gas_limit;
+            return Err(OUT_OF_GAS.to_string());
+        }
+        self.gas_used = used;
+        Ok(())
+    }
+
+    pub fn gas_remaining(&self) -> u64 {
+        self.gas_limit - self.gas_used
+    }
+
+    pub fn gas_refunded(&self) -> u64 {
+        gas::capped_refund(self.refund, self.gas_used)
     }
 
     fn valid_jumpdest(&self, dest: usize) -> bool {
@@ -1,10 +1,27 @@
 use core::panic;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
-use std::io::{BufRead, Stdin, Stdout, Write, stdin, stdout};
+use std::io::{BufRead, Stderr, Stdin, Stdout, Write, stderr, stdin, stdout};
+use std::path::Path;
 use serde::Deserialize;
 use primitive_types::U256;
-use mini_evm::{ContractAccount, Evm, ExitReason};
-use tiny_keccak::{Hasher, Keccak};
+use mini_evm::{ContractAccount, Evm, ExitReason, TxEnv, CONTRACT_ADDRESS, MSG_SENDER};
+use mini_evm::abi::{self, Abi, AbiType, AbiValue, function_selector};
+use mini_evm::abi::json::{AbiFunction, AbiParam, parse_signature};
+use mini_evm::abi::event::format_log;
+use mini_evm::abi::revert::decode_revert;
+use mini_evm::access::create_access_list;
+use mini_evm::artifact::{Artifact, LoadedArtifacts, load_artifacts, select_artifact};
+use mini_evm::debugger::Debugger;
+use mini_evm::estimate::estimate_gas;
+use mini_evm::gas_report::GasReport;
+use mini_evm::inspector::{CallKind, Frame, Inspector, Inspectors};
+use mini_evm::repl::{self, ReplCommand};
+use mini_evm::response::{StateChange, access_list_json, error_json, state_changes, state_changes_json, status, transaction_json};
+use mini_evm::tracing::{CallTracer, ChromeTracer, Eip3155Tracer, PrestateTracer, Profiler, format_address, format_word};
+use mini_evm::transaction::{TxResult, run_to_completion, transact_with};
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
 
 #[derive(Deserialize)]
 #[serde(tag = "type")]
@@ -13,214 +30,933 @@ enum Command {
     Call {
         signature: String,
         args: Vec<String>,
-        input_types: Vec<String>,
-        output_types: Vec<String>,
+        #[serde(default)]
+        input_types: Option<Vec<String>>,
+        #[serde(default)]
+        output_types: Option<Vec<String>>,
+        #[serde(default)]
+        mode: Option<Mode>,
+        #[serde(default)]
+        value: Option<String>,
+    },
+    #[serde(rename = "debug")]
+    Debug {
+        signature: String,
+        args: Vec<String>,
+        #[serde(default)]
+        input_types: Option<Vec<String>>,
+    },
+    #[serde(rename = "access_list")]
+    AccessList {
+        signature: String,
+        args: Vec<String>,
+        #[serde(default)]
+        input_types: Option<Vec<String>>,
+    },
+    #[serde(rename = "estimate_gas")]
+    EstimateGas {
+        signature: String,
+        args: Vec<String>,
+        #[serde(default)]
+        input_types: Option<Vec<String>>,
+    },
+    #[serde(rename = "deploy")]
+    Deploy {
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        input_types: Option<Vec<String>>,
+        #[serde(default)]
+        value: Option<String>,
+        #[serde(default)]
+        contract: Option<String>,
+        #[serde(default)]
+        bytecode: Option<String>,
+    },
+    #[serde(rename = "use")]
+    Use {
+        contract: String,
+    },
+    #[serde(rename = "get_storage")]
+    GetStorage {
+        slot: String,
+        #[serde(default)]
+        address: Option<String>,
+    },
+    #[serde(rename = "set_storage")]
+    SetStorage {
+        slot: String,
+        value: String,
+        #[serde(default)]
+        address: Option<String>,
+    },
+    #[serde(rename = "get_balance")]
+    GetBalance {
+        #[serde(default)]
+        address: Option<String>,
+    },
+    #[serde(rename = "set_balance")]
+    SetBalance {
+        value: String,
+        #[serde(default)]
+        address: Option<String>,
+    },
+    #[serde(rename = "get_nonce")]
+    GetNonce {
+        #[serde(default)]
+        address: Option<String>,
+    },
+    #[serde(rename = "set_nonce")]
+    SetNonce {
+        value: String,
+        #[serde(default)]
+        address: Option<String>,
+    },
+    #[serde(rename = "snapshot")]
+    Snapshot,
+    #[serde(rename = "revert")]
+    Revert {
+        snapshot: usize,
     },
     #[serde(rename = "exit")]
     Exit,
 }
 
-struct EncodedArg {
-    head: Vec<u8>,
-    tail: Vec<u8>,
+// `call` simulates against a copy of the state in a static context, like eth_call, while `send` commits
+// like a mined transaction, advancing the sender's nonce and the block number
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Mode {
+    Call,
+    Send,
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let input: &String = &args[1];
-    let deploy_code: Vec<u8> = hex::decode(input).expect("Error decoding compiled contract");
+#[derive(Clone, Copy, PartialEq)]
+enum TracerKind {
+    Eip3155,
+    Call,
+    Prestate,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ReportFormat {
+    Table,
+    Json,
+}
+
+// everything that watches transactions for the whole session
+struct Tracers {
+    kind: Option<TracerKind>,
+    eip3155: Eip3155Tracer<Stderr>,
+    profiler: Option<Profiler>,
+    chrome: Option<ChromeTracer>,
+}
+
+// a contract that can be deployed: the one given on the command line, the others in its artifact,
+// or bytecode sent along with a deploy command
+#[derive(Clone)]
+struct Template {
+    name: String,
+    abi: Abi,
+    initcode: Vec<u8>,
+}
+
+// every account of the session, contracts and plain addresses alike, and what each contract was
+// deployed from; kept together so that a snapshot is a single clone
+#[derive(Clone, Default)]
+struct World {
+    accounts: BTreeMap<U256, ContractAccount>,
+    contracts: BTreeMap<U256, Template>,
+    active: Option<U256>,
+    block_number: u64,
+}
 
-    let mut account: ContractAccount = ContractAccount::new(deploy_code);
+// the contracts being worked on, and what is collected about them over the session
+struct Session {
+    world: World,
+    snapshots: Vec<World>,
+    templates: Vec<Template>,
+    tracers: Tracers,
+    gas_report: GasReport,
+    show_gas: bool,
+}
+
+impl Session {
+    // the active contract's template, or the command line one while nothing is deployed
+    fn template(&self) -> &Template {
+        self.world.active
+            .and_then(|address: U256| self.world.contracts.get(&address))
+            .unwrap_or(&self.templates[0])
+    }
+
+    fn abi(&self) -> &Abi {
+        &self.template().abi
+    }
 
-    let mut deploy_evm: Evm<'_> = Evm::new(&mut account, U256::zero(), vec![]);
-    let exit: ExitReason = deploy_evm.run().expect("Deployment failed");
+    fn active(&self) -> Result<U256, String> {
+        self.world.active.ok_or("no contract deployed".to_string())
+    }
+
+    fn account(&self) -> Result<&ContractAccount, String> {
+        let address: U256 = self.active()?;
+        Ok(&self.world.accounts[&address])
+    }
+
+    // the environment transactions run in: the session's current block, and static for calls
+    fn env(&self, mode: Mode) -> TxEnv {
+        TxEnv { block_number: self.world.block_number, is_static: mode == Mode::Call, ..TxEnv::default() }
+    }
 
-    match exit {
-        ExitReason::Return(runtime_code) => {
-            account.code = runtime_code;
+    // the mode asked for, otherwise `call` for view and pure functions and `send` for everything else
+    fn mode(&self, signature: &str, requested: Option<Mode>) -> Mode {
+        requested.unwrap_or(match self.abi().function(signature) {
+            Some(function) if matches!(function.state_mutability.as_str(), "view" | "pure") => Mode::Call,
+            _ => Mode::Send,
+        })
+    }
+
+    // the account transactions are sent from
+    fn sender(&self) -> ContractAccount {
+        self.world.accounts.get(&MSG_SENDER).cloned().unwrap_or(ContractAccount { address: MSG_SENDER, ..ContractAccount::new(vec![]) })
+    }
+
+    fn check_funds(&self, value: U256) -> Result<(), String> {
+        let balance: U256 = self.sender().balance;
+        if balance < value {
+            return Err(format!("insufficient funds: the sender has {} wei, {} wei were sent", balance, value));
         }
-        _ => panic!("Deployment failed"),
+        Ok(())
     }
 
-    let stdin: Stdin = stdin();
-    let mut stdout: Stdout = stdout();
+    // every sent transaction is mined in a block of its own, whether or not it succeeded,
+    // and the value it carried leaves the sender when it did
+    fn mine(&mut self, value: U256, success: bool) {
+        let sender: &mut ContractAccount = self.account_mut(MSG_SENDER);
+        sender.nonce += 1;
+        if success {
+            sender.balance -= value;
+        }
+        self.world.block_number += 1;
+    }
 
-    for line  in stdin.lock().lines() {
-        let line: String = line.expect("Could not read stdin line");
-        let cmd: Command = serde_json::from_str(&line).expect("Could not read command from stdin");
+    // the given address, or the active contract's
+    fn address(&self, address: Option<&str>) -> Result<U256, String> {
+        match address {
+            Some(address) => parse_address(address),
+            None => self.active(),
+        }
+    }
 
-        match cmd {
-            Command::Exit => break,
+    // any address can have its balance, nonce or storage set, whether or not it holds code
+    fn account_mut(&mut self, address: U256) -> &mut ContractAccount {
+        self.world.accounts.entry(address).or_insert_with(|| ContractAccount { address, ..ContractAccount::new(vec![]) })
+    }
 
-            Command::Call { signature, args, input_types, output_types } => {
-                let selector: [u8; 4] = function_selector(&signature);
+    // what a deploy command deploys and where: a named contract from the artifact or raw bytecode go to a new
+    // address, otherwise the active contract is deployed afresh in its place
+    fn deploy_target(&self, contract: Option<&str>, bytecode: Option<&str>) -> Result<(Template, U256), String> {
+        let template: Template = match (bytecode, contract) {
+            (Some(bytecode), name) => Template {
+                name: name.unwrap_or("Contract").to_string(),
+                abi: Abi::default(),
+                initcode: hex::decode(bytecode.strip_prefix("0x").unwrap_or(bytecode)).map_err(|e| format!("invalid bytecode: {}", e))?,
+            },
+            (None, Some(name)) => self.templates.iter()
+                .find(|template: &&Template| template.name == name)
+                .cloned()
+                .ok_or(format!("contract {} not found", name))?,
+            (None, None) => return Ok((self.template().clone(), self.world.active.unwrap_or(CONTRACT_ADDRESS))),
+        };
+
+        let mut address: U256 = CONTRACT_ADDRESS;
+        while self.world.accounts.contains_key(&address) {
+            address += U256::one();
+        }
+        Ok((template, address))
+    }
 
-                let mut calldata: Vec<u8> = Vec::new();
-                calldata.extend_from_slice(&selector);
-                
-                let mut heads = Vec::new();
-                let mut tails = Vec::new();
+    // deploys at `address` and makes it the active contract, replacing whatever was there if the deployment succeeds
+    fn deploy(&mut self, template: Template, address: U256, constructor_args: Vec<u8>, value: U256) -> (TxResult, Result<(), String>) {
+        let env: TxEnv = self.env(Mode::Send);
+        let sender: ContractAccount = self.sender();
+        let (deployment, deployed) = deploy_contract(&template, constructor_args, value, address, &env, &sender, &mut self.tracers);
+        self.mine(value, deployed.is_ok());
+        if self.show_gas {
+            report_gas(&deployment);
+        }
 
-                for (arg, ty) in args.iter().zip(input_types.iter()) {
-                    let encoded: EncodedArg = encode_arg(ty, arg);
-                    heads.push(encoded.head);
-                    tails.push(encoded.tail);
+        match deployed {
+            Ok(deployed) => {
+                self.gas_report.record_deployment(&template.name, deployment.gas_used, deployed.code.len());
+                if let Some(chrome) = self.tracers.chrome.as_mut() {
+                    chrome.contract_names.insert(address, template.name.clone());
                 }
+                self.world.accounts.insert(address, deployed);
+                self.world.contracts.insert(address, template);
+                self.world.active = Some(address);
+                (deployment, Ok(()))
+            },
+            Err(e) => (deployment, Err(e)),
+        }
+    }
+
+    // makes a deployed contract the active one, found by address or by name
+    fn select(&mut self, contract: &str) -> Result<U256, String> {
+        if let Ok(address) = parse_address(contract) {
+            if !self.world.contracts.contains_key(&address) {
+                return Err(format!("no contract deployed at {}", contract));
+            }
+            self.world.active = Some(address);
+            return Ok(address);
+        }
 
-                let head_size: usize = 32 * heads.len();
-                let mut current_offset: usize = head_size;
+        let matching: Vec<U256> = self.world.contracts.iter()
+            .filter(|(_, template)| template.name == contract)
+            .map(|(address, _)| *address)
+            .collect();
+        match matching.as_slice() {
+            [address] => {
+                self.world.active = Some(*address);
+                Ok(*address)
+            },
+            [] => Err(format!("no deployed contract named {}", contract)),
+            _ => {
+                let addresses: Vec<String> = matching.iter().map(|address: &U256| format_address(*address)).collect();
+                Err(format!("several {} contracts are deployed, pick one by address: {}", contract, addresses.join(", ")))
+            },
+        }
+    }
 
-                for i in 0..heads.len() {
-                    if !tails[i].is_empty() {
-                        let mut offset_buf: Vec<u8> = vec![0u8; 32];
-                        U256::from(current_offset).to_big_endian(&mut offset_buf);
-                        heads[i] = offset_buf;
-                        current_offset += tails[i].len();
-                    }
+    // runs a function of the active contract, on a copy of its state for `call` and for real for `send`,
+    // and reports the storage slots it changed
+    fn transact(&mut self, signature: &str, calldata: Vec<u8>, value: U256, mode: Mode) -> Result<(TxResult, Vec<StateChange>), String> {
+        let (tx, changes) = self.transact_with(signature, calldata, value, mode, run_to_completion)?;
+        self.settle(&tx, value, mode);
+        // debug sessions are left out of the gas report, which is for calls as they would really run
+        let name: String = self.template().name.clone();
+        self.gas_report.record_call(&name, function_selector(signature), signature, tx.gas_used);
+        Ok((tx, changes))
+    }
+
+    // mines a transaction that went through, and shows its gas when asked to
+    fn settle(&mut self, tx: &TxResult, value: U256, mode: Mode) {
+        if mode == Mode::Send {
+            self.mine(value, tx.success());
+        }
+        if self.show_gas {
+            report_gas(tx);
+        }
+    }
+
+    // `transact` with execution driven by `drive`, as the debugger does, leaving it to the caller to mine the
+    // transaction once it knows it went through with `settle`
+    fn transact_with<F>(&mut self, signature: &str, calldata: Vec<u8>, value: U256, mode: Mode, drive: F) -> Result<(TxResult, Vec<StateChange>), String>
+    where
+        F: for<'e> FnOnce(Evm<'e>) -> (Result<ExitReason, String>, Evm<'e>),
+    {
+        let address: U256 = self.active()?;
+        if mode == Mode::Call && !value.is_zero() {
+            return Err("a read-only call cannot send value, use send instead".to_string());
+        }
+        self.check_funds(value)?;
+        if let Some(chrome) = self.tracers.chrome.as_mut() {
+            chrome.function_names.insert(function_selector(signature), signature.to_string());
+        }
+
+        let before: ContractAccount = self.world.accounts[&address].clone();
+        let mut scratch: ContractAccount = before.clone();
+        let env: TxEnv = self.env(mode);
+        let sender: ContractAccount = self.sender();
+        let account: &mut ContractAccount = match mode {
+            Mode::Send => self.world.accounts.get_mut(&address).expect("active contract has an account"),
+            Mode::Call => &mut scratch,
+        };
+        let tx: TxResult = execute(account, CallKind::Call, value, calldata, &env, &sender, &mut self.tracers, drive);
+        let changes: Vec<StateChange> = state_changes(&before.storage, &account.storage);
+        Ok((tx, changes))
+    }
+
+    // runs a parsed REPL command and returns the text to show; history, help and exit are left to the prompt loop
+    fn repl_command(&mut self, command: ReplCommand) -> String {
+        if self.world.active.is_none() && !matches!(command, ReplCommand::Deploy { .. }) {
+            return "error: no contract deployed, deploy it with: deploy <args>".to_string();
+        }
+
+        match command {
+            ReplCommand::Call { signature, args, value } => self.repl_transact(&signature, &args, value, Some(Mode::Call)),
+            ReplCommand::Send { signature, args, value } => self.repl_transact(&signature, &args, value, None),
+
+            ReplCommand::Deploy { args, value } => {
+                let (template, address) = match self.deploy_target(None, None) {
+                    Ok(target) => target,
+                    Err(e) => return format!("error: {}", e),
+                };
+                let constructor_types: Vec<String> = types_or_abi(None, template.abi.constructor.as_ref().map(|constructor: &AbiFunction| &constructor.inputs));
+                let constructor_args: Vec<u8> = match abi::encode_args(&constructor_types, &args) {
+                    Ok(constructor_args) => constructor_args,
+                    Err(e) => return format!("error: {}", e),
+                };
+                if let Err(e) = self.check_funds(value) {
+                    return format!("error: {}", e);
                 }
+                let name: String = template.name.clone();
+                match self.deploy(template, address, constructor_args, value) {
+                    (deployment, Ok(())) => format!("deployed {} at {} ({} bytes of runtime code, {} gas)",
+                        name, format_address(address), self.world.accounts[&address].code.len(), deployment.gas_used),
+                    (_, Err(e)) => format!("error: deployment failed: {}", e),
+                }
+            },
+
+            ReplCommand::Storage { slot } => match self.account() {
+                Ok(account) => format_word(account.storage.get(&slot).copied().unwrap_or_default()),
+                Err(e) => format!("error: {}", e),
+            },
+            ReplCommand::Balance => match self.account() {
+                Ok(account) => format!("{} wei", account.balance),
+                Err(e) => format!("error: {}", e),
+            },
+            ReplCommand::History | ReplCommand::Help | ReplCommand::Exit => String::new(),
+        }
+    }
 
-                for h in heads {
-                    calldata.extend_from_slice(&h);
+    // the result decoded with the function's output types, followed by one line per event
+    fn repl_transact(&mut self, signature: &str, args: &[String], value: U256, mode: Option<Mode>) -> String {
+        let function: AbiFunction = match repl::resolve_function(self.abi(), signature, args) {
+            Ok(function) => function,
+            Err(e) => return format!("error: {}", e),
+        };
+        let signature: String = function.signature();
+        let input_types: Vec<String> = types_or_abi(None, Some(&function.inputs));
+        let calldata: Vec<u8> = match abi::encode_call(&signature, &input_types, args) {
+            Ok(calldata) => calldata,
+            Err(e) => return format!("error: {}", e),
+        };
+
+        let mode: Mode = self.mode(&signature, mode);
+        let tx: TxResult = match self.transact(&signature, calldata, value, mode) {
+            Ok((tx, _)) => tx,
+            Err(e) => return format!("error: {}", e),
+        };
+        let output_types: Vec<AbiType> = function.outputs.iter().map(|param: &AbiParam| param.ty.clone()).collect();
+        let mut output: String = match tx.result {
+            Ok(ExitReason::Return(ret)) => decode_return(&ret, &output_types),
+            Ok(ExitReason::Revert(data)) => decode_revert(&data, self.abi()),
+            Ok(exit) => format!("{:?}", exit),
+            Err(e) => format!("error: {}", e),
+        };
+        for log in &tx.logs {
+            output.push('\n');
+            output.push_str(&format_log(log, self.abi()));
+        }
+        output
+    }
+
+    // the state commands of the JSON protocol, which read and write accounts directly
+    fn state_command(&mut self, cmd: Command) -> Result<serde_json::Value, String> {
+        match cmd {
+            Command::Use { contract } => {
+                let address: U256 = self.select(&contract)?;
+                Ok(serde_json::json!({ "status": "success", "address": format_address(address), "name": self.template().name }))
+            },
+
+            Command::GetStorage { slot, address } => {
+                let address: U256 = self.address(address.as_deref())?;
+                let slot: U256 = abi::parse_uint(&slot)?;
+                // reads leave missing accounts missing, so they do not take up the next deployment address
+                let value: U256 = self.world.accounts.get(&address)
+                    .and_then(|account: &ContractAccount| account.storage.get(&slot).copied())
+                    .unwrap_or_default();
+                Ok(serde_json::json!({ "status": "success", "value": format_word(value) }))
+            },
+            Command::SetStorage { slot, value, address } => {
+                let address: U256 = self.address(address.as_deref())?;
+                let (slot, value) = (abi::parse_uint(&slot)?, abi::parse_uint(&value)?);
+                let storage: &mut HashMap<U256, U256> = &mut self.account_mut(address).storage;
+                if value.is_zero() {
+                    storage.remove(&slot);
+                } else {
+                    storage.insert(slot, value);
                 }
-                for t in tails {
-                    calldata.extend_from_slice(&t);
+                Ok(serde_json::json!({ "status": "success" }))
+            },
+
+            Command::GetBalance { address } => {
+                let address: U256 = self.address(address.as_deref())?;
+                let balance: U256 = self.world.accounts.get(&address).map(|account: &ContractAccount| account.balance).unwrap_or_default();
+                Ok(serde_json::json!({ "status": "success", "balance": balance.to_string() }))
+            },
+            Command::SetBalance { value, address } => {
+                let address: U256 = self.address(address.as_deref())?;
+                self.account_mut(address).balance = abi::parse_uint(&value)?;
+                Ok(serde_json::json!({ "status": "success" }))
+            },
+
+            Command::GetNonce { address } => {
+                let address: U256 = self.address(address.as_deref())?;
+                let nonce: u64 = self.world.accounts.get(&address).map(|account: &ContractAccount| account.nonce).unwrap_or_default();
+                Ok(serde_json::json!({ "status": "success", "nonce": nonce }))
+            },
+            Command::SetNonce { value, address } => {
+                let address: U256 = self.address(address.as_deref())?;
+                let nonce: U256 = abi::parse_uint(&value)?;
+                if nonce.bits() > 64 {
+                    return Err(format!("nonce out of range: {}", value));
                 }
-                
-                // spin up a new instance of the EVM for every call
-                let mut evm: Evm<'_> = Evm::new(&mut account, U256::zero(), calldata);
-
-                let result: Result<ExitReason, String> = evm.run();
-
-                match result {
-                    Ok(exit) => {
-                        match exit {
-                            ExitReason::Return(ret) => {
-                                decode_return(&mut stdout, ret, output_types);
-                            }
-
-                            _ => {
-                                writeln!(stdout, "{:?}", exit).expect("Error writing Ok to stdout");
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        writeln!(stdout, "error: {}", e).expect("Error writing Err to stdout");
-                    }
+                self.account_mut(address).nonce = nonce.as_u64();
+                Ok(serde_json::json!({ "status": "success" }))
+            },
+
+            // like anvil, reverting to a snapshot also drops it and every snapshot taken after it
+            Command::Snapshot => {
+                self.snapshots.push(self.world.clone());
+                Ok(serde_json::json!({ "status": "success", "snapshot": self.snapshots.len() - 1 }))
+            },
+            Command::Revert { snapshot } => {
+                if snapshot >= self.snapshots.len() {
+                    return Err(format!("unknown snapshot: {}", snapshot));
                 }
+                self.world = self.snapshots[snapshot].clone();
+                self.snapshots.truncate(snapshot);
+                Ok(serde_json::json!({ "status": "success" }))
+            },
 
-                stdout.flush().expect("Error flushing stdout");
-            }
+            _ => unreachable!("not a state command"),
         }
     }
 }
 
-fn function_selector(signature: &str) -> [u8; 4] {
-    let mut keccak: Keccak = Keccak::v256();
-    keccak.update(signature.as_bytes());
-    let mut hash: [u8; 32] = [0u8; 32];
-    keccak.finalize(&mut hash);
-    [hash[0], hash[1], hash[2], hash[3]]
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let input: &String = &args[1];
+    let show_gas: bool = args.iter().any(|arg| arg == "--gas");
+    let report_format: Option<ReportFormat> = parse_report_format(&args);
+
+    // the contract is either hex initcode or a solc/Foundry/Hardhat artifact on disk, whose other
+    // contracts can be deployed alongside it
+    let (artifact, others): (Option<Artifact>, Vec<Artifact>) = if Path::new(input).exists() {
+        let loaded: LoadedArtifacts = load_artifacts(Path::new(input)).expect("Error loading artifacts");
+        for skipped in &loaded.skipped {
+            writeln!(stderr(), "skipped {}", skipped).expect("Error writing to stderr");
+        }
+        let artifacts: Vec<Artifact> = loaded.artifacts;
+        let selected: Artifact = select_artifact(artifacts.clone(), flag_value(&args, "--contract").map(String::as_str)).expect("Error selecting contract");
+        let others: Vec<Artifact> = artifacts.into_iter().filter(|artifact: &Artifact| artifact.name != selected.name).collect();
+        (Some(selected), others)
+    } else {
+        (None, vec![])
+    };
+
+    let contract_name: String = flag_value(&args, "--name").cloned()
+        .or(artifact.as_ref().map(|artifact: &Artifact| artifact.name.clone()))
+        .unwrap_or("Contract".to_string());
+    let abi: Abi = match (flag_value(&args, "--abi"), &artifact) {
+        (Some(path), _) => load_abi(path),
+        (None, Some(artifact)) => artifact.abi.clone(),
+        (None, None) => Abi::default(),
+    };
+    let profile_path: Option<String> = flag_value(&args, "--profile").cloned();
+    let chrome_path: Option<String> = flag_value(&args, "--chrome-trace").cloned();
+    let tracers: Tracers = Tracers {
+        kind: parse_tracer(&args),
+        eip3155: Eip3155Tracer::new(stderr()),
+        profiler: profile_path.as_ref().map(|_| Profiler::new()),
+        chrome: chrome_path.as_ref().map(|_| ChromeTracer::new()),
+    };
+    let initcode: Vec<u8> = match artifact {
+        Some(artifact) => artifact.bytecode,
+        None => hex::decode(input).expect("Error decoding compiled contract"),
+    };
+
+    let mut templates: Vec<Template> = vec![Template { name: contract_name, abi, initcode }];
+    templates.extend(others.into_iter().map(|artifact: Artifact| Template { name: artifact.name, abi: artifact.abi, initcode: artifact.bytecode }));
+    let mut session: Session = Session {
+        world: World { block_number: TxEnv::default().block_number, ..World::default() },
+        snapshots: vec![],
+        templates,
+        tracers,
+        gas_report: GasReport::new(),
+        show_gas,
+    };
+
+    // like anvil, the sender starts out with 10000 ether
+    session.account_mut(MSG_SENDER).balance = U256::exp10(22);
+
+    // a constructor that takes arguments has to wait for a deploy command
+    let template: Template = session.templates[0].clone();
+    if template.abi.constructor.as_ref().is_some_and(|constructor: &AbiFunction| !constructor.inputs.is_empty()) {
+        writeln!(stderr(), "{} takes constructor arguments, deploy it with: deploy <args>", template.name).expect("Error writing to stderr");
+    } else if let (_, Err(e)) = session.deploy(template, CONTRACT_ADDRESS, vec![], U256::zero()) {
+        panic!("Deployment failed: {}", e);
+    }
+
+    if args.iter().any(|arg| arg == "--interactive") {
+        run_repl(&mut session);
+    } else {
+        run_json(&mut session);
+    }
+
+    match report_format {
+        Some(ReportFormat::Table) => write!(stderr(), "{}", session.gas_report.table()).expect("Error writing gas report to stderr"),
+        Some(ReportFormat::Json) => writeln!(stderr(), "{}", session.gas_report.to_json()).expect("Error writing gas report to stderr"),
+        None => {},
+    }
+
+    if let (Some(path), Some(profiler)) = (profile_path, session.tracers.profiler) {
+        std::fs::write(&path, profiler.folded()).expect("Error writing folded stacks");
+        writeln!(stderr(), "{}\nfolded stacks written to {}", profiler.hotspot_table(20), path).expect("Error writing profile to stderr");
+    }
+
+    if let (Some(path), Some(chrome)) = (chrome_path, session.tracers.chrome) {
+        std::fs::write(&path, chrome.to_json().to_string()).expect("Error writing Chrome trace");
+    }
 }
 
-fn encode_arg(ty: &str, value: &str) -> EncodedArg {
-    match ty {
-        "uint256" | "uint" => {
-            let v: U256 = U256::from_dec_str(value).expect("invalid uint256");
-            let mut buf: Vec<u8> = vec![0u8; 32];
-            v.to_big_endian(&mut buf);
-            EncodedArg { head: buf, tail: vec![] }
-        }
+// the line-delimited JSON protocol run.py speaks: one command per line in, one JSON object per line out,
+// carrying the command's `id` when it has one
+fn run_json(session: &mut Session) {
+    let stdin: Stdin = stdin();
+    let mut stdout: Stdout = stdout();
 
-        "address" => {
-            let addr: &str = value.strip_prefix("0x").unwrap_or(value);
-            let raw: Vec<u8> = hex::decode(addr).expect("invalid address");
-            assert_eq!(raw.len(), 20); //uint160
+    let mut lines = stdin.lock().lines();
 
-            let mut buf: Vec<u8> = vec![0u8; 32];
-            buf[12..].copy_from_slice(&raw);
-            EncodedArg { head: buf, tail: vec![] }
+    while let Some(line) = lines.next() {
+        let line: String = line.expect("Could not read stdin line");
+        let request: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                respond(&mut stdout, &serde_json::Value::Null, error_json(&format!("invalid command: {}", e)));
+                continue;
+            },
+        };
+        let id: serde_json::Value = request.get("id").cloned().unwrap_or_default();
+        let cmd: Command = match Command::deserialize(request) {
+            Ok(cmd) => cmd,
+            Err(e) => {
+                respond(&mut stdout, &id, error_json(&format!("invalid command: {}", e)));
+                continue;
+            },
+        };
+
+        let needs_contract: bool = matches!(cmd, Command::Call { .. } | Command::Debug { .. } | Command::AccessList { .. } | Command::EstimateGas { .. });
+        if needs_contract && session.world.active.is_none() {
+            respond(&mut stdout, &id, error_json("no contract deployed"));
+            continue;
         }
 
-        "bool" => {
-            let v: u8 = match value {
-                "true" | "1" => 1u8,
-                "false" | "0" => 0u8,
-                _ => panic!("invalid bool"),
-            };
+        let response: serde_json::Value = match cmd {
+            Command::Exit => break,
+
+            Command::Deploy { args, input_types, value, contract, bytecode } => {
+                let deployed = || -> Result<serde_json::Value, String> {
+                    let (template, address) = session.deploy_target(contract.as_deref(), bytecode.as_deref())?;
+                    let constructor_types: Option<&Vec<AbiParam>> = template.abi.constructor.as_ref().map(|constructor: &AbiFunction| &constructor.inputs);
+                    let constructor_args: Vec<u8> = abi::encode_args(&types_or_abi(input_types, constructor_types), &args)?;
+                    let value: U256 = value.as_deref().map(abi::parse_uint).unwrap_or(Ok(U256::zero()))?;
+                    session.check_funds(value)?;
+
+                    let name: String = template.name.clone();
+                    let (deployment, deployed) = session.deploy(template, address, constructor_args, value);
+                    let deployed: Result<&ContractAccount, String> = deployed.map(|()| &session.world.accounts[&address]);
+                    Ok(deploy_json(&deployment, deployed, &name))
+                };
+                deployed().unwrap_or_else(|e: String| error_json(&e))
+            }
 
-            let mut buf: Vec<u8> = vec![0u8; 32];
-            buf[31] = v;
-            EncodedArg { head: buf, tail: vec![] }
-        }
+            Command::AccessList { signature, args, input_types } => {
+                let account: &ContractAccount = session.account().expect("checked above");
+                match encode_calldata(session.abi(), &signature, &args, input_types)
+                    .and_then(|calldata: Vec<u8>| create_access_list(account, U256::zero(), calldata, &session.env(Mode::Send))) {
+                    Ok(result) => access_list_json(&result),
+                    Err(e) => error_json(&e),
+                }
+            }
 
-        "string" => {
-            let bytes: &[u8] = value.as_bytes();
+            Command::EstimateGas { signature, args, input_types } => {
+                let account: &ContractAccount = session.account().expect("checked above");
+                match encode_calldata(session.abi(), &signature, &args, input_types)
+                    .and_then(|calldata: Vec<u8>| estimate_gas(account, U256::zero(), &calldata, &session.env(Mode::Send))) {
+                    Ok(gas) => serde_json::json!({ "status": "success", "gas": gas }),
+                    Err(e) => error_json(&e),
+                }
+            }
 
-            let mut len_buf: Vec<u8> = vec![0u8; 32];
-            U256::from(bytes.len()).to_big_endian(&mut len_buf);
+            Command::Debug { signature, args, input_types } => {
+                let calldata: Vec<u8> = match encode_calldata(session.abi(), &signature, &args, input_types) {
+                    Ok(calldata) => calldata,
+                    Err(e) => {
+                        respond(&mut stdout, &id, error_json(&e));
+                        continue;
+                    },
+                };
+                // debugger commands are plain lines until `quit`, each answered with the view it produces; the call
+                // is a transaction like any other, committed only when sent and run to completion, while quitting
+                // early leaves no trace of it at all
+                let mode: Mode = session.mode(&signature, None);
+                let mut finished: bool = false;
+                let debugged: Result<(TxResult, Vec<StateChange>), String> = session.transact_with(&signature, calldata, U256::zero(), mode, |mut evm: Evm<'_>| {
+                    let frame: Frame = evm.enter();
+                    let mut debugger: Debugger<'_> = Debugger::new(evm);
+                    respond(&mut stdout, &id, serde_json::json!({ "status": "success", "output": debugger.location() }));
+
+                    for line in lines.by_ref() {
+                        let line: String = line.expect("Could not read stdin line");
+                        if matches!(line.trim(), "quit" | "q") {
+                            break;
+                        }
+                        let output: String = debugger.command(&line);
+                        respond(&mut stdout, &id, serde_json::json!({ "status": "success", "output": output }));
+                    }
 
-            let mut data: Vec<u8> = bytes.to_vec();
-            while data.len() % 32 != 0 {
-                data.push(0);
+                    finished = debugger.exit.is_some();
+                    let exit: Result<ExitReason, String> = debugger.exit.take().unwrap_or(Err("debug session ended before execution finished".to_string()));
+                    let mut evm: Evm<'_> = debugger.evm;
+                    (evm.exit(&frame, exit), evm)
+                });
+                match debugged {
+                    Ok((tx, changes)) if finished => {
+                        session.settle(&tx, U256::zero(), mode);
+                        let committed: bool = mode == Mode::Send && tx.success();
+                        serde_json::json!({
+                            "status": "success",
+                            "committed": committed,
+                            "output": if committed { "debug session ended, changes committed" } else { "debug session ended, changes discarded" },
+                            "gasUsed": tx.gas_used,
+                            "stateChanges": state_changes_json(&changes),
+                        })
+                    },
+                    Ok(_) => serde_json::json!({
+                        "status": "success",
+                        "committed": false,
+                        "output": "debug session quit before execution finished, nothing was sent",
+                        "stateChanges": [],
+                    }),
+                    Err(e) => error_json(&e),
+                }
             }
 
-            EncodedArg {
-                head: vec![0u8; 32], // offset filled later
-                tail: [len_buf, data].concat(),
+            Command::Call { signature, args, input_types, output_types, mode, value } => {
+                let output_types: Result<Vec<AbiType>, String> = types_or_abi(output_types, session.abi().function(&signature).map(|function: &AbiFunction| &function.outputs))
+                    .iter()
+                    .map(|ty: &String| AbiType::parse(ty))
+                    .collect();
+
+                let value: Result<U256, String> = value.as_deref().map(abi::parse_uint).unwrap_or(Ok(U256::zero()));
+
+                match (encode_calldata(session.abi(), &signature, &args, input_types), output_types, value) {
+                    (Ok(calldata), Ok(output_types), Ok(value)) => {
+                        let mode: Mode = session.mode(&signature, mode);
+                        match session.transact(&signature, calldata, value, mode) {
+                            Ok((tx, changes)) => {
+                                let mut response: serde_json::Value = transaction_json(&tx, &output_types, session.abi(), &changes);
+                                response["mode"] = serde_json::json!(if mode == Mode::Call { "call" } else { "send" });
+                                response
+                            },
+                            Err(e) => error_json(&e),
+                        }
+                    },
+                    (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => error_json(&e),
+                }
             }
-        }
 
-        _ => panic!("unsupported type: {}", ty),
+            state => session.state_command(state).unwrap_or_else(|e: String| error_json(&e)),
+        };
+        respond(&mut stdout, &id, response);
     }
 }
 
-fn decode_return(stdout: &mut Stdout, ret: Vec<u8>, output_types: Vec<String>) {
-    if ret.is_empty() {
-        writeln!(stdout, "()").unwrap();
-        return;
+// cast-style commands typed at a prompt, with line editing and history kept across sessions
+fn run_repl(session: &mut Session) {
+    let mut editor: DefaultEditor = DefaultEditor::new().expect("Error starting line editor");
+    let history_path: Option<std::path::PathBuf> = env::var_os("HOME").map(|home| Path::new(&home).join(".mini_evm_history"));
+    if let Some(path) = &history_path {
+        // there is nothing to load the first time round
+        let _ = editor.load_history(path);
+    }
+    let mut history: Vec<String> = vec![];
+
+    println!("{} ready, type 'help' for a list of commands", session.template().name);
+    loop {
+        let line: String = match editor.readline("evm> ") {
+            Ok(line) => line.trim().to_string(),
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => panic!("Error reading input: {}", e),
+        };
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line.as_str());
+        history.push(line.clone());
+
+        let output: String = match repl::parse_command(&line) {
+            Ok(ReplCommand::Exit) => break,
+            Ok(ReplCommand::Help) => repl::HELP.to_string(),
+            Ok(ReplCommand::History) => history.iter().enumerate()
+                .map(|(i, entry)| format!("{:>4}  {}", i + 1, entry))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            Ok(command) => session.repl_command(command),
+            Err(e) => format!("error: {}", e),
+        };
+        println!("{}", output);
     }
 
-    let mut outputs: Vec<String> = Vec::new();
+    if let Some(path) = &history_path {
+        let _ = editor.save_history(path);
+    }
+}
 
-    for (i, ty) in output_types.iter().enumerate() {
-        let head_offset: usize = i * 32;
-        let head: &[u8] = &ret[head_offset..head_offset + 32];
+// writes one response line, tagged with the id of the command it answers
+fn respond(stdout: &mut Stdout, id: &serde_json::Value, mut response: serde_json::Value) {
+    response["id"] = id.clone();
+    writeln!(stdout, "{}", response).expect("Error writing response to stdout");
+    stdout.flush().expect("Error flushing stdout");
+}
 
-        match ty.as_str() {
-            "uint256" | "uint" => {
-                let v: U256 = U256::from_big_endian(head);
-                outputs.push(v.to_string());
-            }
+fn deploy_json(deployment: &TxResult, deployed: Result<&ContractAccount, String>, name: &str) -> serde_json::Value {
+    match deployed {
+        Ok(account) => serde_json::json!({
+            "status": "success",
+            "name": name,
+            "address": format_address(account.address),
+            "codeSize": account.code.len(),
+            "gasUsed": deployment.gas_used,
+        }),
+        // initcode that stops without returning code counts as a failed deployment
+        Err(e) => match status(&deployment.result) {
+            "reverted" => serde_json::json!({ "status": "reverted", "reason": e, "gasUsed": deployment.gas_used }),
+            _ => serde_json::json!({ "status": "failed", "error": e, "gasUsed": deployment.gas_used }),
+        },
+    }
+}
 
-            "bool" => {
-                outputs.push((head[31] == 1).to_string());
-            }
+// a 20-byte address, as the ABI would take it
+fn parse_address(text: &str) -> Result<U256, String> {
+    match AbiType::Address.parse_value(text)? {
+        AbiValue::Address(address) => Ok(address),
+        _ => unreachable!("addresses parse to addresses"),
+    }
+}
 
-            "address" => {
-                let addr: &[u8] = &head[12..32];
-                outputs.push(format!("0x{}", hex::encode(addr)));
-            }
+fn parse_tracer(args: &[String]) -> Option<TracerKind> {
+    if args.iter().any(|arg| arg == "--trace") {
+        return Some(TracerKind::Eip3155);
+    }
+
+    let name: &String = flag_value(args, "--tracer")?;
+    match name.as_str() {
+        "eip3155" => Some(TracerKind::Eip3155),
+        "callTracer" => Some(TracerKind::Call),
+        "prestateTracer" => Some(TracerKind::Prestate),
+        _ => panic!("unknown tracer: {}", name),
+    }
+}
 
-            "string" => {
-                let offset: usize = U256::from_big_endian(head).as_usize();
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    args.iter().skip_while(|arg| *arg != flag).nth(1)
+}
 
-                let len_bytes: &[u8] = &ret[offset..offset + 32];
-                let len: usize = U256::from_big_endian(len_bytes).as_usize();
+fn load_abi(path: &str) -> Abi {
+    let contents: String = std::fs::read_to_string(path).expect("Error reading ABI file");
+    let json: serde_json::Value = serde_json::from_str(&contents).expect("Error parsing ABI file");
+    Abi::from_json(&json).expect("Invalid ABI")
+}
 
-                let data_start: usize = offset + 32;
-                let data: &[u8] = &ret[data_start..data_start + len];
+fn parse_report_format(args: &[String]) -> Option<ReportFormat> {
+    let name: &String = flag_value(args, "--gas-report")?;
+    match name.as_str() {
+        "table" => Some(ReportFormat::Table),
+        "json" => Some(ReportFormat::Json),
+        _ => panic!("unknown gas report format: {}", name),
+    }
+}
 
-                let s: String = String::from_utf8_lossy(data).to_string();
-                outputs.push(format!("\"{}\"", s));
-            }
+// runs the initcode with the encoded constructor arguments appended, and installs the runtime code it returns
+fn deploy_contract(template: &Template, constructor_args: Vec<u8>, value: U256, address: U256, env: &TxEnv, sender: &ContractAccount, tracers: &mut Tracers) -> (TxResult, Result<ContractAccount, String>) {
+    // contracts start out with nonce 1 (EIP-161)
+    let mut account: ContractAccount = ContractAccount { address, nonce: 1, ..ContractAccount::new([&template.initcode[..], &constructor_args].concat()) };
+    let deployment: TxResult = execute(&mut account, CallKind::Create, value, vec![], env, sender, tracers, run_to_completion);
+
+    let deployed: Result<ContractAccount, String> = match &deployment.result {
+        Ok(ExitReason::Return(runtime_code)) => {
+            account.code = runtime_code.clone();
+            Ok(account)
+        },
+        Ok(ExitReason::Revert(data)) => Err(decode_revert(data, &template.abi)),
+        Ok(ExitReason::Stop) => Err("initcode returned no runtime code".to_string()),
+        Err(e) => Err(e.clone()),
+    };
+    (deployment, deployed)
+}
 
-            _ => outputs.push(format!("<unsupported {}>", ty)),
-        }
+// spin up a new instance of the EVM for every call, attaching the requested tracers
+#[allow(clippy::too_many_arguments)]
+fn execute<F>(account: &mut ContractAccount, kind: CallKind, value: U256, calldata: Vec<u8>, env: &TxEnv, sender: &ContractAccount, tracers: &mut Tracers, drive: F) -> TxResult
+where
+    F: for<'e> FnOnce(Evm<'e>) -> (Result<ExitReason, String>, Evm<'e>),
+{
+    let mut call_tracer: CallTracer = CallTracer::new();
+    let mut prestate_tracer: PrestateTracer = PrestateTracer::new(account).with_caller(sender);
+
+    let mut inspectors: Inspectors<'_> = Inspectors(vec![]);
+    match tracers.kind {
+        Some(TracerKind::Eip3155) => inspectors.0.push(&mut tracers.eip3155),
+        Some(TracerKind::Call) => inspectors.0.push(&mut call_tracer),
+        Some(TracerKind::Prestate) => inspectors.0.push(&mut prestate_tracer),
+        None => {},
+    }
+    if let Some(profiler) = tracers.profiler.as_mut() {
+        inspectors.0.push(profiler);
+    }
+    if let Some(chrome) = tracers.chrome.as_mut() {
+        inspectors.0.push(chrome);
     }
 
-    if outputs.len() == 1 {
-        writeln!(stdout, "{}", outputs[0]).expect("Error writing output to stdout");
-    } else {
-        writeln!(stdout, "({})", outputs.join(", ")).expect("Error writing output to stdout");
+    let inspector: Option<&mut dyn Inspector> = if inspectors.0.is_empty() { None } else { Some(&mut inspectors) };
+    let result: TxResult = transact_with(account, kind, value, calldata, env, inspector, drive);
+    call_tracer.finish_transaction(env.gas_limit, result.gas_used);
+
+    let trace: Option<String> = match tracers.kind {
+        Some(TracerKind::Call) => Some(serde_json::to_string(&call_tracer.result()).expect("Error serializing call trace")),
+        Some(TracerKind::Prestate) => Some(serde_json::to_string(&prestate_tracer.diff()).expect("Error serializing prestate diff")),
+        _ => None,
+    };
+    if let Some(trace) = trace {
+        writeln!(stderr(), "{}", trace).expect("Error writing trace to stderr");
+    }
+
+    result
+}
+
+// intrinsic and execution gas are reported apart, the total is what the transaction is charged after refunds and the calldata floor
+fn report_gas(result: &TxResult) {
+    writeln!(stderr(), "gas: intrinsic {} + execution {} - refund {} = {} (calldata floor {})",
+        result.intrinsic_gas, result.execution_gas, result.refund, result.gas_used, result.floor_gas)
+        .expect("Error writing gas report to stderr");
+}
+
+// without types from the command or the ABI, the signature itself says what the arguments are
+fn encode_calldata(abi: &Abi, signature: &str, args: &[String], input_types: Option<Vec<String>>) -> Result<Vec<u8>, String> {
+    let input_types: Vec<String> = match (input_types, abi.function(signature)) {
+        (Some(input_types), _) => input_types,
+        (None, Some(function)) => types_or_abi(None, Some(&function.inputs)),
+        (None, None) => types_or_abi(None, Some(&parse_signature(signature)?.inputs)),
+    };
+    abi::encode_call(signature, &input_types, args)
+}
+
+// types sent along with a command win, otherwise they are looked up in the contract's ABI
+fn types_or_abi(given: Option<Vec<String>>, params: Option<&Vec<AbiParam>>) -> Vec<String> {
+    given.unwrap_or_else(|| {
+        params.map(|params: &Vec<AbiParam>| params.iter().map(|param: &AbiParam| param.ty.to_string()).collect())
+            .unwrap_or_default()
+    })
+}
+
+fn decode_return(ret: &[u8], output_types: &[AbiType]) -> String {
+    match abi::decode(output_types, ret) {
+        Ok(values) if values.len() == 1 => values[0].to_string(),
+        Ok(values) => AbiValue::Tuple(values).to_string(),
+        Err(e) => format!("error: could not decode 0x{}: {}", hex::encode(ret), e),
     }
 }
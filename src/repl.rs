@@ -0,0 +1,206 @@
+use primitive_types::U256;
+use crate::abi::{self, Abi};
+use crate::abi::json::{AbiFunction, parse_signature};
+
+pub const HELP: &str = "\
+call <signature> [args]                   run a function read-only, as eth_call does; writes fail
+send <signature> [args] [--value <wei>]   run a function as a transaction, in a new block (view and pure
+                                          functions are only called)
+deploy [args] [--value <wei>]             deploy a fresh instance with the given constructor arguments
+storage <slot>                            print a storage slot of the contract
+balance                                   print the contract's balance in wei
+history                                   list the commands entered so far
+help                                      show this message
+exit | quit                               leave the REPL
+
+Functions can be named alone (send setNumber 5) when the ABI has them, overloads are told apart by their
+arguments. Full signatures are looked up in the ABI for their return types, or can give them cast-style:
+  call \"balanceOf(address)(uint256)\" 0x1234
+Arguments with spaces are quoted (\"hello world\"), arrays written as [1,2,3] and tuples as (1,0xabc).
+Numbers can be hex (0x10), scientific (1e18), negative for intN, and carry a unit (1.5 ether, 20 gwei).";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplCommand {
+    Call { signature: String, args: Vec<String>, value: U256 },
+    Send { signature: String, args: Vec<String>, value: U256 },
+    Deploy { args: Vec<String>, value: U256 },
+    Storage { slot: U256 },
+    Balance,
+    History,
+    Help,
+    Exit,
+}
+
+// Parses one line typed at the REPL prompt.
+pub fn parse_command(line: &str) -> Result<ReplCommand, String> {
+    let mut words: Vec<String> = tokenize(line)?;
+    if words.is_empty() {
+        return Err("empty command".to_string());
+    }
+    let cmd: String = words.remove(0);
+    let mut words: Vec<String> = join_units(words);
+    let value: U256 = take_value(&mut words)?;
+
+    match cmd.as_str() {
+        "call" | "send" => {
+            if words.is_empty() {
+                return Err(format!("usage: {} <signature> [args] [--value <wei>]", cmd));
+            }
+            let signature: String = unquote(&words.remove(0));
+            if cmd == "call" {
+                Ok(ReplCommand::Call { signature, args: words, value })
+            } else {
+                Ok(ReplCommand::Send { signature, args: words, value })
+            }
+        },
+        "deploy" => Ok(ReplCommand::Deploy { args: words, value }),
+        "storage" => match words.as_slice() {
+            [slot] => Ok(ReplCommand::Storage { slot: abi::parse_uint(slot)? }),
+            _ => Err("usage: storage <slot>".to_string()),
+        },
+        "balance" => Ok(ReplCommand::Balance),
+        "history" => Ok(ReplCommand::History),
+        "help" | "h" => Ok(ReplCommand::Help),
+        "exit" | "quit" | "q" => Ok(ReplCommand::Exit),
+        _ => Err(format!("unknown command: {} (try 'help')", cmd)),
+    }
+}
+
+// Splits a line on whitespace, except inside quotes, brackets and parentheses, so that
+// `"hello world"` and `[1, 2, 3]` each stay one argument. Quotes are kept for the ABI parser to remove.
+pub fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut words: Vec<String> = vec![];
+    let mut current: String = String::new();
+    let mut depth: i32 = 0;
+    let mut quoted: bool = false;
+    let mut escaped: bool = false;
+
+    for c in line.chars() {
+        if quoted {
+            current.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => quoted = false,
+                _ => {},
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                quoted = true;
+                current.push(c);
+            },
+            '[' | '(' => {
+                depth += 1;
+                current.push(c);
+            },
+            ']' | ')' => {
+                depth -= 1;
+                if depth < 0 {
+                    return Err(format!("unbalanced '{}' in: {}", c, line));
+                }
+                current.push(c);
+            },
+            _ if c.is_whitespace() && depth == 0 => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            },
+            _ => current.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(format!("unterminated quote in: {}", line));
+    }
+    if depth != 0 {
+        return Err(format!("unclosed bracket in: {}", line));
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+// The function to call. A full signature is looked up in the ABI, or used as it is when the ABI does not
+// have it or when output types are spelled out. A bare name is looked up by name, and an overloaded one is
+// narrowed down to the variants whose parameters the arguments fit.
+pub fn resolve_function(abi: &Abi, signature: &str, args: &[String]) -> Result<AbiFunction, String> {
+    if !signature.contains('(') {
+        return resolve_name(abi, signature, args).cloned();
+    }
+
+    let parsed: AbiFunction = parse_signature(signature)?;
+    match abi.function(&parsed.signature()) {
+        Some(function) if parsed.outputs.is_empty() => Ok(function.clone()),
+        _ => Ok(parsed),
+    }
+}
+
+fn resolve_name<'a>(abi: &'a Abi, name: &str, args: &[String]) -> Result<&'a AbiFunction, String> {
+    let overloads: Vec<&AbiFunction> = abi.functions.iter().filter(|function: &&AbiFunction| function.name == name).collect();
+    if let [function] = overloads.as_slice() {
+        return Ok(function);
+    }
+    if overloads.is_empty() {
+        return Err(format!("no function named {} in the ABI, give its full signature instead", name));
+    }
+
+    let matching: Vec<&AbiFunction> = overloads.iter()
+        .filter(|function: &&&AbiFunction| accepts(function, args))
+        .copied()
+        .collect();
+    let candidates = |functions: &[&AbiFunction]| -> String {
+        functions.iter().map(|function: &&AbiFunction| function.signature()).collect::<Vec<String>>().join(", ")
+    };
+
+    match matching.as_slice() {
+        [function] => Ok(function),
+        [] => Err(format!("no overload of {} takes these arguments, candidates: {}", name, candidates(&overloads))),
+        _ => Err(format!("{} is ambiguous for these arguments, pick one of: {}", name, candidates(&matching))),
+    }
+}
+
+// whether every argument parses as the type of the parameter it would be passed as
+fn accepts(function: &AbiFunction, args: &[String]) -> bool {
+    function.inputs.len() == args.len()
+        && function.inputs.iter().zip(args.iter()).all(|(param, arg)| param.ty.parse_value(arg).is_ok())
+}
+
+// `1 ether` is typed as two words but meant as one amount
+fn join_units(words: Vec<String>) -> Vec<String> {
+    let mut joined: Vec<String> = vec![];
+    for word in words {
+        let is_unit: bool = matches!(word.to_ascii_lowercase().as_str(), "ether" | "gwei" | "wei");
+        match joined.last_mut() {
+            Some(previous) if is_unit && previous.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') => {
+                previous.push(' ');
+                previous.push_str(&word);
+            },
+            _ => joined.push(word),
+        }
+    }
+    joined
+}
+
+// `--value <wei>` may appear anywhere after the command
+fn take_value(words: &mut Vec<String>) -> Result<U256, String> {
+    let Some(index) = words.iter().position(|word: &String| word == "--value") else {
+        return Ok(U256::zero());
+    };
+    if index + 1 >= words.len() {
+        return Err("--value needs an amount in wei".to_string());
+    }
+    let value: String = words.remove(index + 1);
+    words.remove(index);
+    abi::parse_uint(&value)
+}
+
+fn unquote(word: &str) -> String {
+    match word.strip_prefix('"').and_then(|rest: &str| rest.strip_suffix('"')) {
+        Some(inner) => inner.to_string(),
+        None => word.to_string(),
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use primitive_types::U256;
+use serde_json::{Value, json};
+use crate::{ExitReason, Log};
+use crate::abi::{self, Abi, AbiType, AbiValue};
+use crate::abi::event::{decode_log, format_log};
+use crate::abi::revert::decode_revert;
+use crate::access::{AccessListItem, AccessListResult};
+use crate::tracing::{format_address, format_word};
+use crate::transaction::TxResult;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateChange {
+    pub slot: U256,
+    pub before: U256,
+    pub after: U256,
+}
+
+// Every slot whose value differs between two copies of a contract's storage, in slot order.
+// A slot that is missing holds zero.
+pub fn state_changes(before: &HashMap<U256, U256>, after: &HashMap<U256, U256>) -> Vec<StateChange> {
+    let mut slots: Vec<U256> = before.keys().chain(after.keys()).copied().collect();
+    slots.sort();
+    slots.dedup();
+
+    slots.into_iter()
+        .map(|slot: U256| StateChange {
+            slot,
+            before: before.get(&slot).copied().unwrap_or_default(),
+            after: after.get(&slot).copied().unwrap_or_default(),
+        })
+        .filter(|change: &StateChange| change.before != change.after)
+        .collect()
+}
+
+// `success` when execution returned or stopped, `reverted` for REVERT, `failed` when it halted with an error
+pub fn status(result: &Result<ExitReason, String>) -> &'static str {
+    match result {
+        Ok(ExitReason::Return(_) | ExitReason::Stop) => "success",
+        Ok(ExitReason::Revert(_)) => "reverted",
+        Err(_) => "failed",
+    }
+}
+
+pub fn error_json(message: &str) -> Value {
+    json!({ "status": "error", "error": message })
+}
+
+// The outcome of a transaction: its status, the return values decoded with `output_types`, the raw return
+// data, gas used, logs and storage changes. Reverts carry their decoded reason, failures their error.
+pub fn transaction_json(tx: &TxResult, output_types: &[AbiType], abi: &Abi, changes: &[StateChange]) -> Value {
+    let return_data: &[u8] = match &tx.result {
+        Ok(ExitReason::Return(data) | ExitReason::Revert(data)) => data,
+        _ => &[],
+    };
+
+    let mut response: Value = json!({
+        "status": status(&tx.result),
+        "outputs": Value::Null,
+        "returnData": format!("0x{}", hex::encode(return_data)),
+        "gasUsed": tx.gas_used,
+        "logs": tx.logs.iter().map(|log: &Log| log_json(log, abi)).collect::<Vec<Value>>(),
+        "stateChanges": state_changes_json(changes),
+    });
+
+    match &tx.result {
+        Ok(ExitReason::Return(data)) => match abi::decode(output_types, data) {
+            Ok(values) => response["outputs"] = values.iter().map(AbiValue::to_json).collect(),
+            Err(e) => response["error"] = json!(format!("could not decode return data: {}", e)),
+        },
+        Ok(ExitReason::Stop) => response["outputs"] = json!([]),
+        Ok(ExitReason::Revert(data)) => response["reason"] = json!(decode_revert(data, abi)),
+        Err(e) => response["error"] = json!(e),
+    }
+    response
+}
+
+pub fn state_changes_json(changes: &[StateChange]) -> Value {
+    changes.iter()
+        .map(|change: &StateChange| json!({
+            "slot": format_word(change.slot),
+            "before": format_word(change.before),
+            "after": format_word(change.after),
+        }))
+        .collect()
+}
+
+// Raw topics and data, plus the event name and its fields when the ABI knows the event.
+pub fn log_json(log: &Log, abi: &Abi) -> Value {
+    let mut response: Value = json!({
+        "address": format_address(log.address),
+        "topics": log.topics.iter().map(|topic: &U256| format_word(*topic)).collect::<Vec<String>>(),
+        "data": format!("0x{}", hex::encode(&log.data)),
+        "decoded": format_log(log, abi),
+    });
+
+    if let Some((event, values)) = decode_log(log, abi) {
+        response["event"] = json!(event.name);
+        response["args"] = event.inputs.iter().zip(values.iter()).enumerate()
+            .map(|(i, (param, value))| {
+                let name: String = if param.name.is_empty() { i.to_string() } else { param.name.clone() };
+                (name, value.to_json())
+            })
+            .collect::<serde_json::Map<String, Value>>()
+            .into();
+    }
+    response
+}
+
+pub fn access_list_json(result: &AccessListResult) -> Value {
+    let access_list: Vec<Value> = result.access_list.iter()
+        .map(|item: &AccessListItem| json!({
+            "address": format_address(item.address),
+            "storageKeys": item.storage_keys.iter().map(|key: &U256| format_word(*key)).collect::<Vec<String>>(),
+        }))
+        .collect();
+
+    json!({
+        "status": "success",
+        "accessList": access_list,
+        "gasUsed": result.gas_used,
+        "gasUsedWithoutAccessList": result.gas_used_without,
+    })
+}
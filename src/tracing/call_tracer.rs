@@ -0,0 +1,124 @@
+use primitive_types::U256;
+use serde::Serialize;
+use crate::{ExitReason, Log};
+use crate::inspector::{CallKind, Frame, Inspector};
+use super::{format_address, format_bytes, format_word};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallLog {
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+    pub position: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub from: String,
+    pub to: String,
+    pub value: String,
+    pub gas: String,
+    pub gas_used: String,
+    pub input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallFrame>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<CallLog>,
+}
+
+// Builds the nested call tree in the format of geth's `callTracer` (with `withLog` enabled).
+#[derive(Default)]
+pub struct CallTracer {
+    stack: Vec<CallFrame>,
+    root: Option<CallFrame>,
+}
+
+impl CallTracer {
+    pub fn new() -> Self {
+        CallTracer::default()
+    }
+
+    pub fn result(&self) -> Option<&CallFrame> {
+        self.root.as_ref()
+    }
+
+    // Frames only see execution gas, while geth gives the top-level frame the transaction's gas limit and
+    // the gas its receipt reports, intrinsic gas, refund and calldata floor included.
+    pub fn finish_transaction(&mut self, gas_limit: u64, gas_used: u64) {
+        if let Some(root) = self.root.as_mut() {
+            root.gas = format!("{:#x}", gas_limit);
+            root.gas_used = format!("{:#x}", gas_used);
+        }
+    }
+}
+
+impl Inspector for CallTracer {
+    fn frame_enter(&mut self, frame: &Frame) {
+        let kind: &str = match frame.kind {
+            CallKind::Call => "CALL",
+            CallKind::Create => "CREATE",
+        };
+
+        self.stack.push(CallFrame {
+            kind: kind.to_string(),
+            from: format_address(frame.caller),
+            to: format_address(frame.address),
+            value: format!("{:#x}", frame.value),
+            gas: format!("{:#x}", frame.gas_limit),
+            gas_used: "0x0".to_string(),
+            input: format_bytes(&frame.input),
+            output: None,
+            error: None,
+            calls: vec![],
+            logs: vec![],
+        });
+    }
+
+    fn frame_exit(&mut self, _frame: &Frame, result: &Result<ExitReason, String>, gas_used: u64) {
+        let Some(mut call) = self.stack.pop() else {
+            return;
+        };
+
+        call.gas_used = format!("{:#x}", gas_used);
+        match result {
+            Ok(ExitReason::Return(data)) => call.output = Some(format_bytes(data)),
+            Ok(ExitReason::Stop) => {},
+            Ok(ExitReason::Revert(data)) => {
+                call.output = Some(format_bytes(data));
+                call.error = Some("execution reverted".to_string());
+            },
+            Err(e) => call.error = Some(e.clone()),
+        }
+
+        // logs of a failed frame are discarded, just like the state changes that go with them
+        if call.error.is_some() {
+            call.logs.clear();
+        }
+
+        match self.stack.last_mut() {
+            Some(parent) => parent.calls.push(call),
+            None => self.root = Some(call),
+        }
+    }
+
+    fn log(&mut self, log: &Log) {
+        let Some(call) = self.stack.last_mut() else {
+            return;
+        };
+
+        call.logs.push(CallLog {
+            address: format_address(log.address),
+            topics: log.topics.iter().map(|topic: &U256| format_word(*topic)).collect(),
+            data: format_bytes(&log.data),
+            position: format!("{:#x}", call.calls.len()),
+        });
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::time::Instant;
+use primitive_types::U256;
+use serde::Serialize;
+use crate::{Evm, ExitReason, Log};
+use crate::inspector::{CallKind, Frame, Inspector};
+use super::{format_address, format_bytes, format_word};
+
+const PID: u32 = 1;
+const TID: u32 = 1;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceEvent {
+    pub name: String,
+    pub cat: &'static str,
+    pub ph: &'static str,
+    pub ts: f64,
+    pub pid: u32,
+    pub tid: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s: Option<&'static str>,
+    pub args: serde_json::Value,
+}
+
+// Records execution in the Chrome trace-event format, which chrome://tracing and ui.perfetto.dev open directly:
+// a span per call frame, instant events for logs and storage writes, and a counter track of gas used.
+// Contract and function names are looked up by address and selector, falling back to the raw values.
+pub struct ChromeTracer {
+    pub events: Vec<TraceEvent>,
+    pub contract_names: HashMap<U256, String>,
+    pub function_names: HashMap<[u8; 4], String>,
+    start: Instant,
+    // gas used by the frames that finished before the current transaction, so the counter keeps climbing across a session
+    gas_offset: u64,
+    depth: usize,
+}
+
+impl Default for ChromeTracer {
+    fn default() -> Self {
+        ChromeTracer::new()
+    }
+}
+
+impl ChromeTracer {
+    pub fn new() -> Self {
+        ChromeTracer {
+            events: vec![],
+            contract_names: HashMap::new(),
+            function_names: HashMap::new(),
+            start: Instant::now(),
+            gas_offset: 0,
+            depth: 0,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "traceEvents": self.events,
+            "displayTimeUnit": "ns",
+        })
+    }
+
+    fn timestamp(&self) -> f64 {
+        self.start.elapsed().as_secs_f64() * 1_000_000.0
+    }
+
+    fn push(&mut self, name: String, cat: &'static str, ph: &'static str, args: serde_json::Value) {
+        let s: Option<&'static str> = (ph == "i").then_some("t");
+        let ts: f64 = self.timestamp();
+        self.events.push(TraceEvent { name, cat, ph, ts, pid: PID, tid: TID, s, args });
+    }
+
+    fn frame_name(&self, frame: &Frame) -> String {
+        let contract: String = self.contract_names.get(&frame.address).cloned().unwrap_or(format_address(frame.address));
+        match frame.kind {
+            CallKind::Create => format!("CREATE {}", contract),
+            CallKind::Call if frame.input.len() >= 4 => {
+                let selector: [u8; 4] = [frame.input[0], frame.input[1], frame.input[2], frame.input[3]];
+                let function: String = self.function_names.get(&selector).cloned().unwrap_or(format_bytes(&selector));
+                format!("{}::{}", contract, function)
+            },
+            CallKind::Call => format!("{}::fallback", contract),
+        }
+    }
+}
+
+impl Inspector for ChromeTracer {
+    fn step(&mut self, evm: &Evm<'_>, _opcode: u8) {
+        let gas_used: u64 = self.gas_offset + evm.gas_used;
+        self.push("gas".to_string(), "gas", "C", serde_json::json!({ "used": gas_used }));
+    }
+
+    fn frame_enter(&mut self, frame: &Frame) {
+        self.depth += 1;
+        let name: String = self.frame_name(frame);
+        self.push(name, "call", "B", serde_json::json!({
+            "from": format_address(frame.caller),
+            "to": format_address(frame.address),
+            "value": format!("{:#x}", frame.value),
+            "gas": frame.gas_limit,
+            "input": format_bytes(&frame.input),
+        }));
+    }
+
+    fn frame_exit(&mut self, frame: &Frame, result: &Result<ExitReason, String>, gas_used: u64) {
+        let outcome: String = match result {
+            Ok(ExitReason::Return(_)) => "return".to_string(),
+            Ok(ExitReason::Stop) => "stop".to_string(),
+            Ok(ExitReason::Revert(_)) => "revert".to_string(),
+            Err(e) => e.clone(),
+        };
+
+        self.depth -= 1;
+        if self.depth == 0 {
+            self.gas_offset += gas_used;
+            let total: u64 = self.gas_offset;
+            self.push("gas".to_string(), "gas", "C", serde_json::json!({ "used": total }));
+        }
+
+        let name: String = self.frame_name(frame);
+        self.push(name, "call", "E", serde_json::json!({ "gasUsed": gas_used, "outcome": outcome }));
+    }
+
+    fn sstore(&mut self, address: U256, key: U256, old: U256, new: U256) {
+        self.push("SSTORE".to_string(), "storage", "i", serde_json::json!({
+            "address": format_address(address),
+            "slot": format_word(key),
+            "old": format_word(old),
+            "new": format_word(new),
+        }));
+    }
+
+    fn log(&mut self, log: &Log) {
+        self.push(format!("LOG{}", log.topics.len()), "log", "i", serde_json::json!({
+            "address": format_address(log.address),
+            "topics": log.topics.iter().map(|topic: &U256| format_word(*topic)).collect::<Vec<String>>(),
+            "data": format_bytes(&log.data),
+        }));
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::Write;
+use primitive_types::U256;
+use serde::Serialize;
+use crate::{Evm, ExitReason, opcodes};
+use crate::inspector::{Frame, Inspector};
+use super::format_bytes;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StepLine {
+    pc: usize,
+    op: u8,
+    gas: String,
+    gas_cost: String,
+    mem_size: usize,
+    stack: Vec<String>,
+    depth: usize,
+    return_data: String,
+    refund: String,
+    op_name: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SummaryLine {
+    output: String,
+    gas_used: String,
+    pass: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+struct PendingStep {
+    pc: usize,
+    op: u8,
+    gas: u64,
+    refund: i64,
+    mem_size: usize,
+    stack: Vec<String>,
+}
+
+// Writes one JSON object per executed instruction followed by a summary line, as specified by EIP-3155.
+// The cost of an instruction is only known once the next one starts, so each line is held back a step.
+pub struct Eip3155Tracer<W: Write> {
+    out: W,
+    pending: Option<PendingStep>,
+}
+
+impl<W: Write> Eip3155Tracer<W> {
+    pub fn new(out: W) -> Self {
+        Eip3155Tracer { out, pending: None }
+    }
+
+    fn flush_step(&mut self, gas_remaining: u64) {
+        let Some(step) = self.pending.take() else {
+            return;
+        };
+
+        let line: StepLine = StepLine {
+            pc: step.pc,
+            op: step.op,
+            gas: format!("{:#x}", step.gas),
+            gas_cost: format!("{:#x}", step.gas.saturating_sub(gas_remaining)),
+            mem_size: step.mem_size,
+            stack: step.stack,
+            depth: 1,
+            return_data: "0x".to_string(),
+            refund: format!("{:#x}", step.refund.max(0)),
+            op_name: opcodes::name(step.op),
+        };
+        self.write_line(&line);
+    }
+
+    fn write_line<T: Serialize>(&mut self, line: &T) {
+        let json: String = serde_json::to_string(line).expect("Error serializing trace line");
+        writeln!(self.out, "{}", json).expect("Error writing trace line");
+    }
+}
+
+impl<W: Write> Inspector for Eip3155Tracer<W> {
+    fn step(&mut self, evm: &Evm<'_>, opcode: u8) {
+        let gas: u64 = evm.gas_remaining();
+        self.flush_step(gas);
+
+        self.pending = Some(PendingStep {
+            pc: evm.pc,
+            op: opcode,
+            gas,
+            refund: evm.refund,
+            mem_size: evm.memory_words * 32,
+            stack: evm.stack.iter().map(|v: &U256| format!("{:#x}", v)).collect(),
+        });
+    }
+
+    fn frame_enter(&mut self, _frame: &Frame) {
+        self.pending = None;
+    }
+
+    fn frame_exit(&mut self, frame: &Frame, result: &Result<ExitReason, String>, gas_used: u64) {
+        self.flush_step(frame.gas_limit - gas_used);
+
+        let (output, pass, error) = match result {
+            Ok(ExitReason::Return(data)) => (data.clone(), true, None),
+            Ok(ExitReason::Stop) => (vec![], true, None),
+            Ok(ExitReason::Revert(data)) => (data.clone(), false, Some("execution reverted".to_string())),
+            Err(e) => (vec![], false, Some(e.clone())),
+        };
+
+        let summary: SummaryLine = SummaryLine {
+            output: format_bytes(&output),
+            gas_used: format!("{:#x}", gas_used),
+            pass,
+            error,
+        };
+        self.write_line(&summary);
+        self.out.flush().expect("Error flushing trace output");
+    }
+}
@@ -0,0 +1,29 @@
+use primitive_types::U256;
+
+pub mod call_tracer;
+pub mod chrome;
+pub mod eip3155;
+pub mod prestate;
+pub mod profiler;
+
+pub use call_tracer::CallTracer;
+pub use chrome::ChromeTracer;
+pub use eip3155::Eip3155Tracer;
+pub use prestate::PrestateTracer;
+pub use profiler::Profiler;
+
+pub fn format_address(address: U256) -> String {
+    let mut buf: [u8; 32] = [0u8; 32];
+    address.to_big_endian(&mut buf);
+    format!("0x{}", hex::encode(&buf[12..]))
+}
+
+pub fn format_word(word: U256) -> String {
+    let mut buf: [u8; 32] = [0u8; 32];
+    word.to_big_endian(&mut buf);
+    format!("0x{}", hex::encode(buf))
+}
+
+pub fn format_bytes(data: &[u8]) -> String {
+    format!("0x{}", hex::encode(data))
+}
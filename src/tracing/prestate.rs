@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use primitive_types::U256;
+use serde::Serialize;
+use crate::{ContractAccount, ExitReason};
+use crate::inspector::{CallKind, Frame, Inspector};
+use super::{format_address, format_bytes, format_word};
+
+#[derive(Debug, Default, Serialize)]
+pub struct AccountState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrestateDiff {
+    pub pre: BTreeMap<String, AccountState>,
+    pub post: BTreeMap<String, AccountState>,
+}
+
+// Records the state of the executing account and its caller as geth's `prestateTracer` does. The tracer
+// is built from the accounts before the account is handed to the `Evm`, since storage is only observed
+// through hooks. The value sent along is taken to move from the caller to the account when execution succeeds.
+pub struct PrestateTracer {
+    balance: U256,
+    code: Vec<u8>,
+    caller_balance: U256,
+    address: Option<U256>,
+    caller: Option<U256>,
+    value: U256,
+    succeeded: bool,
+    created: bool,
+    runtime_code: Option<Vec<u8>>,
+    original: BTreeMap<U256, U256>,
+    current: BTreeMap<U256, U256>,
+}
+
+impl PrestateTracer {
+    pub fn new(account: &ContractAccount) -> Self {
+        PrestateTracer {
+            balance: account.balance,
+            code: account.code.clone(),
+            caller_balance: U256::zero(),
+            address: None,
+            caller: None,
+            value: U256::zero(),
+            succeeded: false,
+            created: false,
+            runtime_code: None,
+            original: BTreeMap::new(),
+            current: BTreeMap::new(),
+        }
+    }
+
+    // the caller's balance before the transaction, zero unless given
+    pub fn with_caller(mut self, caller: &ContractAccount) -> Self {
+        self.caller_balance = caller.balance;
+        self
+    }
+
+    // Default mode: the pre-state of every account and slot the execution touched.
+    pub fn prestate(&self) -> BTreeMap<String, AccountState> {
+        let mut pre: BTreeMap<String, AccountState> = BTreeMap::new();
+        if let Some(caller) = self.caller {
+            pre.insert(format_address(caller), AccountState { balance: Some(format!("{:#x}", self.caller_balance)), ..AccountState::default() });
+        }
+        let (Some(address), false) = (self.address, self.created) else {
+            return pre;
+        };
+
+        pre.insert(format_address(address), AccountState {
+            balance: Some(format!("{:#x}", self.balance)),
+            code: Some(format_bytes(&self.code)),
+            storage: Self::format_storage(self.original.iter()),
+        });
+        pre
+    }
+
+    // Diff mode: only the accounts and fields that changed, before and after.
+    pub fn diff(&self) -> PrestateDiff {
+        let mut diff: PrestateDiff = PrestateDiff { pre: BTreeMap::new(), post: BTreeMap::new() };
+        let Some(address) = self.address else {
+            return diff;
+        };
+
+        let changed: Vec<(&U256, &U256)> = self.current.iter()
+            .filter(|(key, value)| self.original.get(key) != Some(value))
+            .collect();
+        let transferred: bool = self.succeeded && !self.value.is_zero();
+        let balance: U256 = if transferred { self.balance.saturating_add(self.value) } else { self.balance };
+
+        if let (Some(caller), true) = (self.caller, transferred) {
+            diff.pre.insert(format_address(caller), AccountState { balance: Some(format!("{:#x}", self.caller_balance)), ..AccountState::default() });
+            diff.post.insert(format_address(caller), AccountState {
+                balance: Some(format!("{:#x}", self.caller_balance.saturating_sub(self.value))),
+                ..AccountState::default()
+            });
+        }
+
+        if self.created {
+            diff.post.insert(format_address(address), AccountState {
+                balance: Some(format!("{:#x}", balance)),
+                code: self.runtime_code.as_ref().map(|code: &Vec<u8>| format_bytes(code)),
+                storage: Self::format_storage(changed.into_iter()),
+            });
+            return diff;
+        }
+
+        if changed.is_empty() && !transferred {
+            return diff;
+        }
+
+        diff.pre.insert(format_address(address), AccountState {
+            balance: Some(format!("{:#x}", self.balance)),
+            code: Some(format_bytes(&self.code)),
+            storage: Self::format_storage(changed.iter().map(|(key, _)| (*key, &self.original[*key]))),
+        });
+        diff.post.insert(format_address(address), AccountState {
+            balance: transferred.then(|| format!("{:#x}", balance)),
+            code: None,
+            storage: Self::format_storage(changed.into_iter()),
+        });
+        diff
+    }
+
+    fn format_storage<'s, I: Iterator<Item = (&'s U256, &'s U256)>>(slots: I) -> BTreeMap<String, String> {
+        slots
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(key, value)| (format_word(*key), format_word(*value)))
+            .collect()
+    }
+
+    fn touch(&mut self, key: U256, value: U256) {
+        self.original.entry(key).or_insert(value);
+        self.current.entry(key).or_insert(value);
+    }
+}
+
+impl Inspector for PrestateTracer {
+    fn frame_enter(&mut self, frame: &Frame) {
+        if self.address.is_none() {
+            self.address = Some(frame.address);
+            self.caller = Some(frame.caller);
+            self.value = frame.value;
+            self.created = frame.kind == CallKind::Create;
+        }
+    }
+
+    fn frame_exit(&mut self, frame: &Frame, result: &Result<ExitReason, String>, _gas_used: u64) {
+        self.succeeded = matches!(result, Ok(ExitReason::Return(_) | ExitReason::Stop));
+        match result {
+            Ok(ExitReason::Return(code)) if frame.kind == CallKind::Create => {
+                self.runtime_code = Some(code.clone());
+            },
+            Ok(ExitReason::Return(_)) | Ok(ExitReason::Stop) => {},
+            // nothing is committed by a failed frame
+            _ => self.current = self.original.clone(),
+        }
+    }
+
+    fn sload(&mut self, _address: U256, key: U256, value: U256) {
+        self.touch(key, value);
+    }
+
+    fn sstore(&mut self, _address: U256, key: U256, old: U256, new: U256) {
+        self.touch(key, old);
+        self.current.insert(key, new);
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use crate::{Evm, ExitReason, opcodes};
+use crate::inspector::{CallKind, Frame, Inspector};
+use super::{format_address, format_bytes};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OpcodeStats {
+    pub count: u64,
+    pub gas: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hotspot {
+    pub frame: String,
+    pub pc: usize,
+    pub opcode: u8,
+    pub stats: OpcodeStats,
+}
+
+struct PendingStep {
+    pc: usize,
+    opcode: u8,
+    gas_used: u64,
+}
+
+struct ProfiledFrame {
+    label: String,
+    pending: Option<PendingStep>,
+}
+
+// Counts executed instructions and the gas they cost, per opcode and per pc of each frame.
+// Like the EIP-3155 tracer it only learns what an instruction cost once the next one starts.
+#[derive(Default)]
+pub struct Profiler {
+    pub opcodes: BTreeMap<u8, OpcodeStats>,
+    pub pcs: BTreeMap<(String, usize), (u8, OpcodeStats)>,
+    // gas spent under each `frame;frame;OPCODE` path, the input flamegraph tools expect
+    pub stacks: BTreeMap<String, u64>,
+    frames: Vec<ProfiledFrame>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler::default()
+    }
+
+    fn record(&mut self, gas_used: u64) {
+        let Some(frame) = self.frames.last_mut() else {
+            return;
+        };
+        let Some(step) = frame.pending.take() else {
+            return;
+        };
+        let gas: u64 = gas_used.saturating_sub(step.gas_used);
+
+        let stats: &mut OpcodeStats = self.opcodes.entry(step.opcode).or_default();
+        stats.count += 1;
+        stats.gas += gas;
+
+        let (_, stats) = self.pcs.entry((frame.label.clone(), step.pc)).or_insert((step.opcode, OpcodeStats::default()));
+        stats.count += 1;
+        stats.gas += gas;
+
+        let path: String = self.frames.iter()
+            .map(|frame: &ProfiledFrame| frame.label.as_str())
+            .chain(std::iter::once(opcodes::name(step.opcode)))
+            .collect::<Vec<&str>>()
+            .join(";");
+        *self.stacks.entry(path).or_default() += gas;
+    }
+
+    // One `frame;frame;OPCODE gas` line per path, ready for flamegraph.pl or inferno.
+    pub fn folded(&self) -> String {
+        self.stacks.iter()
+            .map(|(path, gas)| format!("{} {}\n", path, gas))
+            .collect()
+    }
+
+    // Instructions ordered by the gas they used, most expensive first.
+    pub fn hotspots(&self) -> Vec<Hotspot> {
+        let mut hotspots: Vec<Hotspot> = self.pcs.iter()
+            .map(|((frame, pc), (opcode, stats))| Hotspot { frame: frame.clone(), pc: *pc, opcode: *opcode, stats: *stats })
+            .collect();
+        hotspots.sort_by(|a: &Hotspot, b: &Hotspot| b.stats.gas.cmp(&a.stats.gas).then(b.stats.count.cmp(&a.stats.count)));
+        hotspots
+    }
+
+    pub fn hotspot_table(&self, limit: usize) -> String {
+        let total: u64 = self.opcodes.values().map(|stats: &OpcodeStats| stats.gas).sum();
+        let mut lines: Vec<String> = vec![format!("{:>8} {:>7} {:>6}  {:<14} {:<6}  frame", "gas", "%", "count", "opcode", "pc")];
+
+        for hotspot in self.hotspots().into_iter().take(limit) {
+            let share: f64 = if total == 0 { 0.0 } else { hotspot.stats.gas as f64 * 100.0 / total as f64 };
+            lines.push(format!("{:>8} {:>6.2}% {:>6}  {:<14} {:<#6x}  {}",
+                hotspot.stats.gas, share, hotspot.stats.count, opcodes::name(hotspot.opcode), hotspot.pc, hotspot.frame));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Inspector for Profiler {
+    fn step(&mut self, evm: &Evm<'_>, opcode: u8) {
+        self.record(evm.gas_used);
+        if let Some(frame) = self.frames.last_mut() {
+            frame.pending = Some(PendingStep { pc: evm.pc, opcode, gas_used: evm.gas_used });
+        }
+    }
+
+    fn frame_enter(&mut self, frame: &Frame) {
+        let label: String = match frame.kind {
+            CallKind::Create => format!("CREATE {}", format_address(frame.address)),
+            CallKind::Call if frame.input.len() >= 4 => format!("{}::{}", format_address(frame.address), format_bytes(&frame.input[..4])),
+            CallKind::Call => format_address(frame.address),
+        };
+        self.frames.push(ProfiledFrame { label, pending: None });
+    }
+
+    fn frame_exit(&mut self, _frame: &Frame, _result: &Result<ExitReason, String>, gas_used: u64) {
+        self.record(gas_used);
+        self.frames.pop();
+    }
+}
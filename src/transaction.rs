@@ -0,0 +1,100 @@
+use primitive_types::U256;
+use crate::{ContractAccount, Evm, ExitReason, Log, TxEnv, gas};
+use crate::inspector::{CallKind, Inspector};
+
+#[derive(Debug)]
+pub struct TxResult {
+    pub result: Result<ExitReason, String>,
+    pub intrinsic_gas: u64,
+    pub floor_gas: u64,
+    pub execution_gas: u64,
+    pub refund: u64,
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+}
+
+impl TxResult {
+    pub fn success(&self) -> bool {
+        matches!(self.result, Ok(ExitReason::Return(_) | ExitReason::Stop))
+    }
+}
+
+// Runs a whole transaction: intrinsic gas is charged up front, execution gets what is left of the
+// gas limit, the refund is capped against the total, and the EIP-7623 calldata floor applies last.
+// For a creation the initcode is the account's code, and it is what the calldata costs are charged on.
+// The value is credited to the account, and a failed transaction leaves storage and balance as they were.
+pub fn transact(account: &mut ContractAccount, kind: CallKind, callvalue: U256, calldata: Vec<u8>, env: &TxEnv, inspector: Option<&mut dyn Inspector>) -> TxResult {
+    transact_with(account, kind, callvalue, calldata, env, inspector, run_to_completion)
+}
+
+// A transaction whose execution is driven by `drive`, which gets the ready `Evm` and hands it back along with
+// how execution ended; the debugger steps through it this way instead of running it in one go.
+pub fn transact_with<F>(account: &mut ContractAccount, kind: CallKind, callvalue: U256, calldata: Vec<u8>, env: &TxEnv, inspector: Option<&mut dyn Inspector>, drive: F) -> TxResult
+where
+    F: for<'e> FnOnce(Evm<'e>) -> (Result<ExitReason, String>, Evm<'e>),
+{
+    let is_create: bool = kind == CallKind::Create;
+    let data: &[u8] = if is_create { &account.code } else { &calldata };
+
+    let intrinsic_gas: u64 = gas::intrinsic_gas(data, is_create, &env.access_list);
+    let floor_gas: u64 = gas::floor_gas(data);
+
+    if env.gas_limit < intrinsic_gas.max(floor_gas) {
+        return TxResult {
+            result: Err(format!("intrinsic gas too low: have {}, want {}", env.gas_limit, intrinsic_gas.max(floor_gas))),
+            intrinsic_gas,
+            floor_gas,
+            execution_gas: 0,
+            refund: 0,
+            gas_used: 0,
+            logs: vec![],
+        };
+    }
+
+    let mut execution_env: TxEnv = env.clone();
+    execution_env.gas_limit = env.gas_limit - intrinsic_gas;
+
+    let (result, execution_gas, evm_refund, logs, original_storage) = {
+        let mut evm: Evm<'_> = Evm::with_env(account, callvalue, calldata, &execution_env);
+        evm.contract_balance = evm.contract_balance.saturating_add(callvalue);
+        evm.kind = kind;
+        if let Some(inspector) = inspector {
+            evm.inspector = Some(inspector);
+        }
+        let (result, evm) = drive(evm);
+        (result, evm.gas_used, evm.refund, evm.logs, evm.original_storage)
+    };
+    let success: bool = matches!(result, Ok(ExitReason::Return(_) | ExitReason::Stop));
+
+    let refund: u64 = gas::capped_refund(evm_refund, intrinsic_gas + execution_gas);
+    let gas_used: u64 = (intrinsic_gas + execution_gas - refund).max(floor_gas);
+
+    // logs of a failed transaction are discarded along with its state changes
+    let logs: Vec<Log> = if success { logs } else { vec![] };
+    if success {
+        account.balance = account.balance.saturating_add(callvalue);
+    } else {
+        for (key, value) in original_storage {
+            if value.is_zero() {
+                account.storage.remove(&key);
+            } else {
+                account.storage.insert(key, value);
+            }
+        }
+    }
+
+    TxResult {
+        result,
+        intrinsic_gas,
+        floor_gas,
+        execution_gas,
+        refund,
+        gas_used,
+        logs,
+    }
+}
+
+pub fn run_to_completion(mut evm: Evm<'_>) -> (Result<ExitReason, String>, Evm<'_>) {
+    let result: Result<ExitReason, String> = evm.run();
+    (result, evm)
+}
@@ -0,0 +1,197 @@
+use mini_evm::abi::{AbiType, AbiValue, decode, encode, encode_call, parse_uint};
+use primitive_types::U256;
+
+fn words(hex_words: &[&str]) -> Vec<u8> {
+    hex_words.iter()
+        .flat_map(|word: &&str| hex::decode(format!("{:0>64}", word)).unwrap())
+        .collect()
+}
+
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value: &&str| value.to_string()).collect()
+}
+
+#[test]
+fn encodes_dynamic_arguments() {
+    // the example from the Solidity ABI specification
+    let types: Vec<String> = args(&["uint256", "uint32[]", "bytes10", "bytes"]);
+    let calldata: Vec<u8> = encode_call("f(uint256,uint32[],bytes10,bytes)", &types,
+        &args(&["0x123", "[0x456,0x789]", "0x31323334353637383930", "0x48656c6c6f2c20776f726c6421"])).unwrap();
+
+    let expected: Vec<u8> = [hex::decode("8be65246").unwrap(), words(&[
+        "123", "80", "3132333435363738393000000000000000000000000000000000000000000000", "e0",
+        "2", "456", "789",
+        "d", "48656c6c6f2c20776f726c642100000000000000000000000000000000000000",
+    ])].concat();
+    assert_eq!(calldata, expected);
+}
+
+#[test]
+fn encodes_nested_dynamic_arrays() {
+    let types: Vec<String> = args(&["uint256[][]", "string[]"]);
+    let calldata: Vec<u8> = encode_call("g(uint256[][],string[])", &types, &args(&["[[1,2],[3]]", "[one,two,\"three\"]"])).unwrap();
+
+    let expected: Vec<u8> = [hex::decode("2289b18c").unwrap(), words(&[
+        "40", "140",
+        "2", "40", "a0", "2", "1", "2", "1", "3",
+        "3", "60", "a0", "e0",
+        "3", "6f6e650000000000000000000000000000000000000000000000000000000000",
+        "3", "74776f0000000000000000000000000000000000000000000000000000000000",
+        "5", "7468726565000000000000000000000000000000000000000000000000000000",
+    ])].concat();
+    assert_eq!(calldata, expected);
+}
+
+#[test]
+fn parses_types_and_values() {
+    let ty: AbiType = AbiType::parse("(int8,bool,address[2])[]").unwrap();
+    assert_eq!(ty.to_string(), "(int8,bool,address[2])[]");
+    assert!(ty.is_dynamic());
+
+    let value: AbiValue = AbiType::parse("int8").unwrap().parse_value("-1").unwrap();
+    assert_eq!(value, AbiValue::Int(U256::MAX));
+
+    assert!(AbiType::parse("int8").unwrap().parse_value("-129").is_err());
+    assert!(AbiType::parse("uint8").unwrap().parse_value("256").is_err());
+    assert!(AbiType::parse("bytes2").unwrap().parse_value("0x123456").is_err());
+    assert!(AbiType::parse("uint7").is_err());
+    assert!(encode_call("h(uint256)", &args(&["uint256"]), &[]).is_err());
+}
+
+#[test]
+fn parses_number_literals() {
+    let ether: U256 = U256::exp10(18);
+    assert_eq!(parse_uint("0x10").unwrap(), U256::from(16));
+    assert_eq!(parse_uint("1e18").unwrap(), ether);
+    assert_eq!(parse_uint("1 ether").unwrap(), ether);
+    assert_eq!(parse_uint("1.5ether").unwrap(), ether * 3 / 2);
+    assert_eq!(parse_uint("2.5e3 gwei").unwrap(), U256::from(2_500_000_000_000u64));
+    assert_eq!(parse_uint("100e-2").unwrap(), U256::one());
+    assert_eq!(parse_uint("7 wei").unwrap(), U256::from(7));
+
+    assert!(parse_uint("0.5").unwrap_err().contains("not a whole number"));
+    assert!(parse_uint("1e78").unwrap_err().contains("does not fit"));
+    assert!(parse_uint("ether").unwrap_err().starts_with("invalid number"));
+    assert!(parse_uint("12abc").unwrap_err().starts_with("invalid number"));
+    assert!(parse_uint("0x").unwrap_err().starts_with("invalid number"));
+    assert!(parse_uint("0x ether").unwrap_err().starts_with("invalid number"));
+
+    let int256: AbiType = AbiType::parse("int256").unwrap();
+    assert_eq!(int256.parse_value("-1 gwei").unwrap(), AbiValue::Int(U256::zero().overflowing_sub(U256::exp10(9)).0));
+    assert!(AbiType::parse("int16").unwrap().parse_value("-4e4").is_err());
+    assert!(AbiType::parse("uint64").unwrap().parse_value("100 ether").is_err());
+    assert_eq!(AbiType::parse("bytes32").unwrap().parse_value("0xff").unwrap(), AbiValue::FixedBytes([vec![0xff], vec![0; 31]].concat()));
+}
+
+#[test]
+fn decodes_what_it_encodes() {
+    let types: Vec<AbiType> = ["uint256", "uint32[]", "bytes10", "bytes", "(int8,string)[2]"].iter()
+        .map(|ty: &&str| AbiType::parse(ty).unwrap())
+        .collect();
+    let literals: [&str; 5] = ["0x123", "[0x456,0x789]", "0x31323334353637383930", "0x48656c6c6f", "[(-5,\"a, b\"),(7,c)]"];
+    let values: Vec<AbiValue> = types.iter().zip(literals.iter())
+        .map(|(ty, literal)| ty.parse_value(literal).unwrap())
+        .collect();
+
+    let decoded: Vec<AbiValue> = decode(&types, &encode(&values)).unwrap();
+    assert_eq!(decoded, values);
+
+    let shown: String = AbiValue::Tuple(decoded.clone()).to_string();
+    assert_eq!(shown, "(291, [1110, 1929], 0x31323334353637383930, 0x48656c6c6f, [(-5, \"a, b\"), (7, \"c\")])");
+    assert_eq!(decoded[4].to_json(), serde_json::json!([["-5", "a, b"], ["7", "c"]]));
+}
+
+#[test]
+fn rejects_malformed_return_data() {
+    let uint8: Vec<AbiType> = vec![AbiType::parse("uint8").unwrap()];
+    assert!(decode(&uint8, &words(&["100"])).is_err());
+    assert!(decode(&uint8, &words(&["ff"])[..31]).is_err());
+
+    let int8: Vec<AbiType> = vec![AbiType::Int(8)];
+    assert_eq!(decode(&int8, &words(&["ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff80"])).unwrap()[0].to_string(), "-128");
+    assert!(decode(&int8, &words(&["80"])).is_err());
+
+    assert!(decode(&[AbiType::Bool], &words(&["2"])).is_err());
+
+    // an offset past the end, and a length longer than the data
+    assert!(decode(&[AbiType::String], &words(&["1000"])).is_err());
+    assert!(decode(&[AbiType::String], &words(&["20", "40", "6869"])).is_err());
+    assert!(decode(&[AbiType::Array(Box::new(AbiType::Uint(256)))], &words(&["20", "40"])).is_err());
+}
+
+#[test]
+fn decodes_revert_reasons() {
+    use mini_evm::abi::Abi;
+    use mini_evm::abi::revert::decode_revert;
+
+    let abi: Abi = Abi::from_json(&serde_json::json!([{
+        "type": "error",
+        "name": "InsufficientBalance",
+        "inputs": [{ "name": "available", "type": "uint256" }, { "name": "required", "type": "uint256" }],
+    }])).unwrap();
+
+    let error: Vec<u8> = encode_call("Error(string)", &args(&["string"]), &args(&["Not owner"])).unwrap();
+    assert_eq!(decode_revert(&error, &abi), "execution reverted: Not owner");
+
+    let panic: Vec<u8> = encode_call("Panic(uint256)", &args(&["uint256"]), &args(&["0x11"])).unwrap();
+    assert_eq!(decode_revert(&panic, &abi), "panic: arithmetic underflow or overflow (0x11)");
+
+    let custom: Vec<u8> = encode_call("InsufficientBalance(uint256,uint256)", &args(&["uint256", "uint256"]), &args(&["1", "5"])).unwrap();
+    assert_eq!(decode_revert(&custom, &abi), "execution reverted: InsufficientBalance(available=1, required=5)");
+    assert_eq!(decode_revert(&custom, &Abi::default()), format!("execution reverted: 0x{}", hex::encode(&custom)));
+
+    assert_eq!(decode_revert(&[], &abi), "execution reverted");
+}
+
+#[test]
+fn decodes_events() {
+    use mini_evm::Log;
+    use mini_evm::abi::Abi;
+    use mini_evm::abi::event::format_log;
+
+    let abi: Abi = Abi::from_json(&serde_json::json!([
+        {
+            "type": "event", "name": "Transfer", "anonymous": false,
+            "inputs": [
+                { "name": "from", "type": "address", "indexed": true },
+                { "name": "to", "type": "address", "indexed": true },
+                { "name": "value", "type": "uint256", "indexed": false },
+            ],
+        },
+        {
+            "type": "event", "name": "Named", "anonymous": false,
+            "inputs": [{ "name": "name", "type": "string", "indexed": true }],
+        },
+        {
+            "type": "event", "name": "Ping", "anonymous": true,
+            "inputs": [{ "name": "id", "type": "uint8", "indexed": false }],
+        },
+    ])).unwrap();
+
+    let transfer: Log = Log {
+        address: mini_evm::CONTRACT_ADDRESS,
+        topics: vec![
+            U256::from_str_radix("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef", 16).unwrap(),
+            U256::from(0xaa),
+            U256::from(0xbb),
+        ],
+        data: words(&["64"]),
+    };
+    assert_eq!(format_log(&transfer, &abi), format!(
+        "Transfer(from=0x{:0>40}, to=0x{:0>40}, value=100)", "aa", "bb"));
+
+    // an indexed string only leaves its hash behind
+    let hash: [u8; 32] = mini_evm::abi::keccak256(b"alice");
+    let named: Log = Log {
+        address: mini_evm::CONTRACT_ADDRESS,
+        topics: vec![U256::from(mini_evm::abi::keccak256(b"Named(string)")), U256::from(hash)],
+        data: vec![],
+    };
+    assert_eq!(format_log(&named, &abi), format!("Named(name=0x{})", hex::encode(hash)));
+
+    let ping: Log = Log { address: mini_evm::CONTRACT_ADDRESS, topics: vec![], data: words(&["7"]) };
+    assert_eq!(format_log(&ping, &abi), "Ping(id=7)");
+
+    let unknown: Log = Log { address: mini_evm::CONTRACT_ADDRESS, topics: vec![U256::one()], data: vec![] };
+    assert!(format_log(&unknown, &abi).starts_with("Log(address=0x00000000adddecaf"));
+}
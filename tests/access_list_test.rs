@@ -0,0 +1,21 @@
+use mini_evm::{ContractAccount, TxEnv, CONTRACT_ADDRESS};
+use mini_evm::access::{AccessListItem, AccessListResult, create_access_list};
+use primitive_types::U256;
+mod common;
+
+#[test]
+fn lists_touched_slots() {
+    // PUSH1 3, SLOAD, PUSH1 0x42, BALANCE, PUSH1 1, PUSH1 5, SSTORE, STOP
+    let code: Vec<u8> = vec![0x60, 0x03, 0x54, 0x60, 0x42, 0x31, 0x60, 0x01, 0x60, 0x05, 0x55, 0x00];
+    let account: ContractAccount = common::setup(code);
+
+    let result: AccessListResult = create_access_list(&account, U256::zero(), vec![], &TxEnv::default()).unwrap();
+
+    assert_eq!(result.access_list, vec![
+        AccessListItem { address: U256::from(0x42), storage_keys: vec![] },
+        AccessListItem { address: CONTRACT_ADDRESS, storage_keys: vec![U256::from(3), U256::from(5)] },
+    ]);
+    // listing the recipient costs 2400, the two slots and 0x42 each save 100 (SSTORE 200) more than they cost
+    assert_eq!(result.gas_used, result.gas_used_without + 2_400 - 100 - 200 - 100);
+    assert!(account.storage.is_empty());
+}
@@ -0,0 +1,104 @@
+use std::fs;
+use std::path::PathBuf;
+use mini_evm::artifact::{Artifact, LoadedArtifacts, SourceMapEntry, load_artifacts, parse_source_map, select_artifact};
+use primitive_types::U256;
+
+const ABI: &str = r#"[{"type":"function","name":"number","inputs":[],"outputs":[{"name":"","type":"uint256"}],"stateMutability":"view"}]"#;
+
+fn write_fixture(dir: &str, file: &str, contents: &str) -> PathBuf {
+    let dir: PathBuf = std::env::temp_dir().join(format!("mini-evm-{}-{}", dir, std::process::id()));
+    let path: PathBuf = dir.join(file);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn loads_solc_combined_json() {
+    let json: String = format!(r#"{{"contracts": {{
+        "src/A.sol:A": {{"abi": {abi}, "bin": "6001", "bin-runtime": "6002", "srcmap": "0:10:0:-;;5:::i", "srcmap-runtime": "",
+            "storage-layout": {{"storage": [{{"label": "number", "slot": "0", "offset": 0, "type": "t_uint256"}}],
+                                "types": {{"t_uint256": {{"label": "uint256"}}}}}}}},
+        "src/A.sol:B": {{"abi": "{escaped}", "bin": "", "bin-runtime": ""}}
+    }}}}"#, abi = ABI, escaped = ABI.replace('"', "\\\""));
+    let path: PathBuf = write_fixture("solc", "combined.json", &json);
+
+    let artifacts: Vec<Artifact> = load_artifacts(&path).unwrap().artifacts;
+    assert_eq!(artifacts.len(), 2);
+    assert!(select_artifact(artifacts.clone(), None).is_err());
+
+    let a: Artifact = select_artifact(artifacts.clone(), Some("A")).unwrap();
+    assert_eq!((a.bytecode, a.deployed_bytecode), (vec![0x60, 0x01], vec![0x60, 0x02]));
+    assert_eq!(a.abi.functions[0].signature(), "number()");
+    assert_eq!(a.source_map.len(), 3);
+    assert_eq!(a.storage_layout[0].label, "number");
+    assert_eq!(a.storage_layout[0].slot, U256::zero());
+    assert_eq!(a.storage_layout[0].ty, "uint256");
+
+    // older solc versions give the ABI as a string
+    let b: Artifact = select_artifact(artifacts, Some("B")).unwrap();
+    assert_eq!(b.abi.functions.len(), 1);
+}
+
+#[test]
+fn loads_foundry_and_hardhat_artifacts() {
+    let foundry: String = format!(r#"{{"abi": {}, "bytecode": {{"object": "0x6001", "sourceMap": "1:2:0"}},
+        "deployedBytecode": {{"object": "0x6002", "sourceMap": ""}}}}"#, ABI);
+    let path: PathBuf = write_fixture("foundry", "out/Counter.sol/Counter.json", &foundry);
+    write_fixture("foundry", "out/build-info/abc.json", "{}");
+
+    let artifacts: Vec<Artifact> = load_artifacts(path.parent().unwrap().parent().unwrap()).unwrap().artifacts;
+    assert_eq!(artifacts.len(), 1);
+    assert_eq!(artifacts[0].name, "Counter");
+    assert_eq!(artifacts[0].deployed_bytecode, vec![0x60, 0x02]);
+
+    let hardhat: String = format!(r#"{{"_format": "hh-sol-artifact-1", "contractName": "Counter", "sourceName": "contracts/Counter.sol",
+        "abi": {}, "bytecode": "0x6001", "deployedBytecode": "0x6002", "linkReferences": {{}}}}"#, ABI);
+    let path: PathBuf = write_fixture("hardhat", "Counter.json", &hardhat);
+    let artifacts: Vec<Artifact> = load_artifacts(&path).unwrap().artifacts;
+    assert_eq!(artifacts[0].name, "Counter");
+    assert_eq!(artifacts[0].bytecode, vec![0x60, 0x01]);
+
+    let unlinked: String = r#"{"_format": "hh-sol-artifact-1", "contractName": "L", "abi": [], "bytecode": "0x73__$abc$__", "deployedBytecode": "0x"}"#.to_string();
+    assert!(load_artifacts(&write_fixture("hardhat", "L.json", &unlinked)).is_err());
+}
+
+#[test]
+fn skips_and_reports_artifacts_that_fail_to_load() {
+    let json: String = format!(r#"{{"contracts": {{
+        "src/A.sol:A": {{"abi": {}, "bin": "6001", "bin-runtime": "6002"}},
+        "src/A.sol:Broken": {{"abi": [], "bin": "zz", "bin-runtime": ""}}
+    }}}}"#, ABI);
+    let loaded: LoadedArtifacts = load_artifacts(&write_fixture("skipped", "combined.json", &json)).unwrap();
+    assert_eq!(loaded.artifacts.len(), 1);
+    assert_eq!(loaded.artifacts[0].name, "A");
+    assert_eq!(loaded.skipped.len(), 1);
+    assert!(loaded.skipped[0].starts_with("src/A.sol:Broken: invalid bytecode"));
+
+    // in a directory, a broken artifact is reported while other JSON files are passed over
+    let unlinked: String = r#"{"abi": [], "bytecode": {"object": "0x73__$abc$__"}, "deployedBytecode": {"object": "0x"}}"#.to_string();
+    let path: PathBuf = write_fixture("skipped", "out/L.sol/L.json", &unlinked);
+    write_fixture("skipped", "out/cache.json", r#"{"paths": {}}"#);
+    let out: &std::path::Path = path.parent().unwrap().parent().unwrap();
+    assert!(load_artifacts(out).unwrap_err().contains("L.json: bytecode has unlinked library references"));
+
+    write_fixture("skipped", "out/A.sol/A.json", &format!(r#"{{"abi": {}, "bytecode": {{"object": "0x6001"}}}}"#, ABI));
+    let loaded: LoadedArtifacts = load_artifacts(out).unwrap();
+    assert_eq!(loaded.artifacts.len(), 1);
+    assert_eq!(loaded.skipped.len(), 1);
+
+    // a corrupt artifact is reported too, rather than passed over like JSON that is not an artifact
+    write_fixture("skipped", "out/B.sol/B.json", r#"{"abi": [], "bytecode": "#);
+    let loaded: LoadedArtifacts = load_artifacts(out).unwrap();
+    assert_eq!(loaded.skipped.len(), 2);
+    assert!(loaded.skipped.iter().any(|skipped: &String| skipped.starts_with("could not parse") && skipped.contains("B.json")));
+}
+
+#[test]
+fn decompresses_source_maps() {
+    let entries: Vec<SourceMapEntry> = parse_source_map("0:10:0:-;;5:::i;:3:1:o").unwrap();
+    let fields: Vec<(i64, i64, i64, char)> = entries.iter()
+        .map(|entry: &SourceMapEntry| (entry.offset, entry.length, entry.file, entry.jump))
+        .collect();
+    assert_eq!(fields, vec![(0, 10, 0, '-'), (0, 10, 0, '-'), (5, 10, 0, 'i'), (5, 3, 1, 'o')]);
+}
@@ -0,0 +1,133 @@
+use mini_evm::{Evm, ContractAccount, ExitReason, TxEnv};
+use mini_evm::debugger::{Breakpoint, Debugger};
+use mini_evm::inspector::{CallKind, Frame};
+use mini_evm::transaction::{TxResult, transact, transact_with};
+use primitive_types::U256;
+mod common;
+
+// SLOAD slot 0, add one, SSTORE it back, STOP
+fn counter_code() -> Vec<u8> {
+    vec![0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x00]
+}
+
+#[test]
+fn steps_one_instruction_at_a_time() {
+    let mut account: ContractAccount = common::setup(counter_code());
+    let evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+    let mut debugger: Debugger = Debugger::new(evm);
+
+    debugger.step();
+    debugger.step();
+    assert_eq!(debugger.evm.pc, 3);
+    assert_eq!(debugger.evm.stack, vec![U256::zero()]);
+    assert!(!debugger.finished());
+
+    assert_eq!(debugger.command("s 10"), "execution finished: Stop");
+    assert!(matches!(debugger.exit, Some(Ok(ExitReason::Stop))));
+}
+
+#[test]
+fn continues_to_breakpoints() {
+    let mut account: ContractAccount = common::setup(counter_code());
+    let evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+    let mut debugger: Debugger = Debugger::new(evm);
+
+    debugger.command("break slot 0");
+    debugger.command("b pc 0x5");
+    assert_eq!(debugger.breakpoints, vec![Breakpoint::Slot(U256::zero()), Breakpoint::Pc(5)]);
+
+    assert_eq!(debugger.resume(), Some(0));
+    assert_eq!(debugger.current_opcode(), Some(0x54));
+
+    assert_eq!(debugger.resume(), Some(1));
+    assert_eq!(debugger.evm.pc, 5);
+
+    assert_eq!(debugger.resume(), Some(0));
+    assert_eq!(debugger.current_opcode(), Some(0x55));
+    assert_eq!(debugger.storage_dump(), "<empty storage>");
+
+    assert_eq!(debugger.resume(), None);
+    assert_eq!(debugger.storage_dump(), "0x0: 0x1");
+}
+
+#[test]
+fn steps_backwards_through_the_journal() {
+    // PUSH1 0x2A, PUSH1 0, MSTORE, PUSH1 7, PUSH1 1, SSTORE, PUSH1 9, PUSH1 1, SSTORE, STOP
+    let code: Vec<u8> = vec![0x60, 0x2A, 0x60, 0x00, 0x52, 0x60, 0x07, 0x60, 0x01, 0x55, 0x60, 0x09, 0x60, 0x01, 0x55, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+    let mut debugger: Debugger = Debugger::new(evm);
+
+    debugger.resume();
+    assert!(debugger.finished());
+    assert_eq!(debugger.journal.len(), 10);
+
+    let (step, delta) = debugger.last_write(U256::one()).unwrap();
+    assert_eq!(step, 8);
+    assert_eq!(delta.pc, 14);
+
+    debugger.goto(8);
+    assert!(!debugger.finished());
+    assert_eq!(debugger.evm.storage[&U256::one()], U256::from(7));
+    assert_eq!(debugger.evm.stack, vec![U256::from(9), U256::one()]);
+
+    debugger.goto(2);
+    assert!(debugger.evm.storage.is_empty());
+    assert!(debugger.evm.memory.is_empty());
+    assert_eq!(debugger.evm.gas_used, 6);
+
+    assert!(debugger.step_back());
+    assert!(debugger.step_back());
+    assert!(!debugger.step_back());
+    assert_eq!(debugger.evm.pc, 0);
+    assert!(debugger.evm.stack.is_empty());
+
+    debugger.goto(10);
+    assert!(matches!(debugger.exit, Some(Ok(ExitReason::Stop))));
+    assert_eq!(debugger.evm.storage[&U256::one()], U256::from(9));
+}
+
+#[test]
+fn steps_back_over_code_copied_into_memory() {
+    // PUSH1 0xFF, PUSH1 0, MSTORE8, PUSH1 4, PUSH1 0, PUSH1 0, ADDRESS, EXTCODECOPY, STOP
+    let code: Vec<u8> = vec![0x60, 0xFF, 0x60, 0x00, 0x53, 0x60, 0x04, 0x60, 0x00, 0x60, 0x00, 0x30, 0x3C, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+    let mut debugger: Debugger = Debugger::new(evm);
+
+    debugger.resume();
+    assert_eq!(debugger.evm.memory[..4], [0x60, 0xFF, 0x60, 0x00]);
+
+    debugger.goto(7);
+    assert_eq!(debugger.current_opcode(), Some(0x3C));
+    assert_eq!(debugger.evm.memory, vec![0xFF]);
+}
+
+#[test]
+fn debugged_transactions_match_regular_ones() {
+    let mut regular: ContractAccount = common::setup(counter_code());
+    let expected: TxResult = transact(&mut regular, CallKind::Call, U256::zero(), vec![], &TxEnv::default(), None);
+
+    let mut account: ContractAccount = common::setup(counter_code());
+    let tx: TxResult = transact_with(&mut account, CallKind::Call, U256::zero(), vec![], &TxEnv::default(), None, |mut evm: Evm<'_>| {
+        let frame: Frame = evm.enter();
+        let mut debugger: Debugger = Debugger::new(evm);
+        debugger.command("c");
+        let exit: Result<ExitReason, String> = debugger.exit.take().unwrap();
+        let mut evm: Evm<'_> = debugger.evm;
+        (evm.exit(&frame, exit), evm)
+    });
+    assert_eq!(tx.gas_used, expected.gas_used);
+    assert_eq!(account.storage, regular.storage);
+
+    // a session left before execution finished is rolled back like any failed transaction
+    let tx: TxResult = transact_with(&mut account, CallKind::Call, U256::zero(), vec![], &TxEnv::default(), None, |mut evm: Evm<'_>| {
+        let frame: Frame = evm.enter();
+        let mut debugger: Debugger = Debugger::new(evm);
+        debugger.command("s 9");
+        let mut evm: Evm<'_> = debugger.evm;
+        (evm.exit(&frame, Err("debug session ended".to_string())), evm)
+    });
+    assert!(!tx.success());
+    assert_eq!(account.storage[&U256::zero()], U256::one());
+}
@@ -0,0 +1,192 @@
+use mini_evm::{Evm, ContractAccount, TxEnv, CONTRACT_ADDRESS, OUT_OF_GAS};
+use mini_evm::access::AccessListItem;
+use mini_evm::inspector::CallKind;
+use mini_evm::estimate::estimate_gas;
+use mini_evm::gas_report::{FunctionGas, GasReport};
+use mini_evm::transaction::{TxResult, transact};
+use primitive_types::U256;
+mod common;
+
+#[test]
+fn charges_static_costs() {
+    // PUSH1 2, PUSH1 3, ADD, STOP
+    let code: Vec<u8> = vec![0x60, 0x02, 0x60, 0x03, 0x01, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+
+    evm.run().unwrap();
+    assert_eq!(evm.gas_used, 9);
+}
+
+#[test]
+fn charges_memory_expansion() {
+    // PUSH1 1, PUSH1 0x20, MSTORE, STOP -> two words of memory
+    let code: Vec<u8> = vec![0x60, 0x01, 0x60, 0x20, 0x52, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+
+    evm.run().unwrap();
+    assert_eq!(evm.memory_words, 2);
+    assert_eq!(evm.gas_used, 3 + 3 + 3 + 6);
+}
+
+#[test]
+fn runs_out_of_gas() {
+    let code: Vec<u8> = vec![0x60, 0x02, 0x60, 0x03, 0x01, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+    evm.gas_limit = 5;
+
+    assert_eq!(evm.run().unwrap_err(), OUT_OF_GAS);
+    assert_eq!(evm.gas_used, 5);
+}
+
+#[test]
+fn sstore_net_metering() {
+    // PUSH1 1, PUSH1 0, SSTORE (set), PUSH1 2, PUSH1 0, SSTORE (dirty), STOP
+    let code: Vec<u8> = vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x02, 0x60, 0x00, 0x55, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+
+    evm.run().unwrap();
+    assert_eq!(evm.gas_used, 4 * 3 + 2_100 + 20_000 + 100);
+    assert_eq!(evm.refund, 0);
+}
+
+#[test]
+fn sstore_clear_refund_is_capped() {
+    // PUSH1 0, PUSH1 0, SSTORE (clear a non-zero slot), STOP
+    let code: Vec<u8> = vec![0x60, 0x00, 0x60, 0x00, 0x55, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    account.storage.insert(U256::zero(), U256::from(5));
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+
+    evm.run().unwrap();
+    assert_eq!(evm.gas_used, 2 * 3 + 2_100 + 2_900);
+    assert_eq!(evm.refund, 4_800);
+    assert_eq!(evm.gas_refunded(), evm.gas_used / 5);
+    assert!(account.storage.is_empty());
+}
+
+#[test]
+fn sstore_sentry() {
+    let code: Vec<u8> = vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+    evm.gas_limit = 2_306;
+
+    assert_eq!(evm.run().unwrap_err(), OUT_OF_GAS);
+    assert!(account.storage.is_empty());
+}
+
+#[test]
+fn cold_then_warm_sload() {
+    // PUSH1 0, SLOAD, PUSH1 0, SLOAD, STOP
+    let code: Vec<u8> = vec![0x60, 0x00, 0x54, 0x60, 0x00, 0x54, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+
+    evm.run().unwrap();
+    assert_eq!(evm.gas_used, 3 + 2_100 + 3 + 100);
+}
+
+#[test]
+fn access_list_prewarms_slots() {
+    let code: Vec<u8> = vec![0x60, 0x00, 0x54, 0x60, 0x00, 0x54, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let env: TxEnv = TxEnv {
+        access_list: vec![AccessListItem { address: CONTRACT_ADDRESS, storage_keys: vec![U256::zero()] }],
+        ..TxEnv::default()
+    };
+    let mut evm: Evm = Evm::with_env(&mut account, U256::zero(), vec![], &env);
+
+    evm.run().unwrap();
+    assert_eq!(evm.gas_used, 3 + 100 + 3 + 100);
+}
+
+#[test]
+fn cold_account_access() {
+    // PUSH1 0x42, BALANCE, ADDRESS, BALANCE, PUSH1 0x42, EXTCODESIZE, STOP
+    let code: Vec<u8> = vec![0x60, 0x42, 0x31, 0x30, 0x31, 0x60, 0x42, 0x3b, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+
+    evm.run().unwrap();
+    assert_eq!(evm.gas_used, 3 + 2_600 + 2 + 100 + 3 + 100);
+    assert_eq!(evm.stack, vec![U256::zero(), U256::zero(), U256::zero()]);
+}
+
+#[test]
+fn estimates_gas_above_the_sentry() {
+    // PUSH1 1, PUSH1 0, SSTORE, STOP
+    let code: Vec<u8> = vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00];
+    let account: ContractAccount = common::setup(code);
+
+    let estimate: u64 = estimate_gas(&account, U256::zero(), &[], &TxEnv::default()).unwrap();
+    assert_eq!(estimate, 21_000 + 3 + 3 + 2_100 + 20_000);
+    assert!(account.storage.is_empty());
+
+    // PUSH1 0, SLOAD, POP, PUSH1 0, PUSH1 0, SSTORE (no-op), STOP only uses 2211 gas,
+    // but the SSTORE needs more than 2300 gas left to run at all
+    let code: Vec<u8> = vec![0x60, 0x00, 0x54, 0x50, 0x60, 0x00, 0x60, 0x00, 0x55, 0x00];
+    let account: ContractAccount = common::setup(code);
+
+    let estimate: u64 = estimate_gas(&account, U256::zero(), &[], &TxEnv::default()).unwrap();
+    assert_eq!(estimate, 21_000 + 3 + 2_100 + 2 + 3 + 3 + 2_301);
+}
+
+#[test]
+fn charges_intrinsic_gas() {
+    // PUSH1 0, CALLDATALOAD, STOP
+    let code: Vec<u8> = vec![0x60, 0x00, 0x35, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let env: TxEnv = TxEnv {
+        access_list: vec![AccessListItem { address: U256::from(0x42), storage_keys: vec![U256::one()] }],
+        ..TxEnv::default()
+    };
+
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::zero(), vec![0x00, 0x00, 0x01, 0x02], &env, None);
+    assert_eq!(tx.intrinsic_gas, 21_000 + 2 * 4 + 2 * 16 + 2_400 + 1_900);
+    assert_eq!(tx.execution_gas, 3 + 3);
+    assert_eq!(tx.gas_used, tx.intrinsic_gas + tx.execution_gas);
+
+    // creation pays for the initcode as calldata, plus 32000 and 2 per initcode word
+    let mut account: ContractAccount = common::setup(vec![0x00; 33]);
+    let tx: TxResult = transact(&mut account, CallKind::Create, U256::zero(), vec![], &TxEnv::default(), None);
+    assert_eq!(tx.intrinsic_gas, 21_000 + 33 * 4 + 32_000 + 2 * 2);
+
+    let env: TxEnv = TxEnv { gas_limit: 20_999, ..TxEnv::default() };
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::zero(), vec![], &env, None);
+    assert!(tx.result.is_err());
+}
+
+#[test]
+fn applies_calldata_floor() {
+    // STOP
+    let mut account: ContractAccount = common::setup(vec![0x00]);
+    let calldata: Vec<u8> = vec![0xff; 100];
+
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::zero(), calldata.clone(), &TxEnv::default(), None);
+    assert_eq!(tx.intrinsic_gas, 21_000 + 100 * 16);
+    assert_eq!(tx.floor_gas, 21_000 + 100 * 4 * 10);
+    assert_eq!(tx.gas_used, tx.floor_gas);
+
+    // the limit has to cover the floor even though execution would fit in less
+    let env: TxEnv = TxEnv { gas_limit: tx.floor_gas - 1, ..TxEnv::default() };
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::zero(), calldata, &env, None);
+    assert!(tx.result.is_err());
+}
+
+#[test]
+fn aggregates_gas_per_function() {
+    let mut report: GasReport = GasReport::new();
+    report.record_deployment("Counter", 60_000, 100);
+    for gas_used in [43_000, 23_000, 26_000, 30_000] {
+        report.record_call("Counter", [0xd0, 0x9d, 0xe0, 0x8a], "increment()", gas_used);
+    }
+
+    let function: &FunctionGas = &report.contracts["Counter"].functions[&[0xd0, 0x9d, 0xe0, 0x8a]];
+    assert_eq!((function.min(), function.mean(), function.median(), function.max()), (23_000, 30_500, 30_000, 43_000));
+    assert_eq!(report.to_json()["Counter"]["functions"]["0xd09de08a"]["calls"], 4);
+    assert_eq!(report.to_json()["Counter"]["deploymentCost"], 60_000);
+}
@@ -0,0 +1,72 @@
+use mini_evm::{Evm, ContractAccount, ExitReason, Log};
+use mini_evm::inspector::{CallKind, Frame, Inspector};
+use primitive_types::U256;
+mod common;
+
+#[derive(Default)]
+struct Recorder {
+    opcodes: Vec<u8>,
+    pcs: Vec<usize>,
+    frames: Vec<CallKind>,
+    exits: usize,
+    sstores: Vec<(U256, U256, U256)>,
+    logs: Vec<Log>,
+}
+
+impl Inspector for Recorder {
+    fn step(&mut self, evm: &Evm<'_>, opcode: u8) {
+        self.opcodes.push(opcode);
+        self.pcs.push(evm.pc);
+    }
+
+    fn frame_enter(&mut self, frame: &Frame) {
+        self.frames.push(frame.kind);
+    }
+
+    fn frame_exit(&mut self, _frame: &Frame, _result: &Result<ExitReason, String>, _gas_used: u64) {
+        self.exits += 1;
+    }
+
+    fn sstore(&mut self, _address: U256, key: U256, old: U256, new: U256) {
+        self.sstores.push((key, old, new));
+    }
+
+    fn log(&mut self, log: &Log) {
+        self.logs.push(log.clone());
+    }
+}
+
+#[test]
+fn observes_steps_and_storage() {
+    // PUSH1 0x0A, PUSH1 0x01, SSTORE, STOP
+    let code: Vec<u8> = vec![0x60, 0x0A, 0x60, 0x01, 0x55, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut recorder: Recorder = Recorder::default();
+
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+    evm.inspector = Some(&mut recorder);
+    evm.run().unwrap();
+
+    assert_eq!(recorder.opcodes, vec![0x60, 0x60, 0x55, 0x00]);
+    assert_eq!(recorder.pcs, vec![0, 2, 4, 5]);
+    assert_eq!(recorder.frames, vec![CallKind::Call]);
+    assert_eq!(recorder.exits, 1);
+    assert_eq!(recorder.sstores, vec![(U256::from(1), U256::zero(), U256::from(10))]);
+}
+
+#[test]
+fn observes_logs() {
+    // PUSH1 0xFF, PUSH1 0x00, MSTORE8, PUSH1 0x42, PUSH1 0x01, PUSH1 0x00, LOG1, STOP
+    let code: Vec<u8> = vec![0x60, 0xFF, 0x60, 0x00, 0x53, 0x60, 0x42, 0x60, 0x01, 0x60, 0x00, 0xa1, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut recorder: Recorder = Recorder::default();
+
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+    evm.inspector = Some(&mut recorder);
+    evm.run().unwrap();
+    assert_eq!(evm.logs.len(), 1);
+
+    assert_eq!(recorder.logs.len(), 1);
+    assert_eq!(recorder.logs[0].topics, vec![U256::from(0x42)]);
+    assert_eq!(recorder.logs[0].data, vec![0xFF]);
+}
@@ -0,0 +1,84 @@
+use mini_evm::abi::{Abi, AbiType};
+use mini_evm::abi::json::AbiFunction;
+use mini_evm::repl::{ReplCommand, parse_command, resolve_function, tokenize};
+use primitive_types::U256;
+
+#[test]
+fn tokenizes_quotes_and_brackets() {
+    let words: Vec<String> = tokenize(r#"call setName(string,uint256[]) "hello \"big\" world" [1, 2, 3]"#).unwrap();
+    assert_eq!(words, vec!["call", "setName(string,uint256[])", r#""hello \"big\" world""#, "[1, 2, 3]"]);
+
+    assert!(tokenize(r#"call f(string) "open"#).is_err());
+    assert!(tokenize("call f(uint256[]) [1, 2").is_err());
+    assert!(tokenize("call f(uint256[]) 1]").is_err());
+}
+
+#[test]
+fn parses_cast_style_commands() {
+    assert_eq!(parse_command("send increment() --value 0x10").unwrap(), ReplCommand::Send {
+        signature: "increment()".to_string(),
+        args: vec![],
+        value: U256::from(16),
+    });
+    assert_eq!(parse_command(r#"call "balanceOf(address)(uint256)" 0x1234"#).unwrap(), ReplCommand::Call {
+        signature: "balanceOf(address)(uint256)".to_string(),
+        args: vec!["0x1234".to_string()],
+        value: U256::zero(),
+    });
+    assert_eq!(parse_command("deploy 5 (1,0xab)").unwrap(), ReplCommand::Deploy {
+        args: vec!["5".to_string(), "(1,0xab)".to_string()],
+        value: U256::zero(),
+    });
+    assert_eq!(parse_command("storage 0x02").unwrap(), ReplCommand::Storage { slot: U256::from(2) });
+    assert_eq!(parse_command("send deposit(uint256) 2 gwei --value 1 ether").unwrap(), ReplCommand::Send {
+        signature: "deposit(uint256)".to_string(),
+        args: vec!["2 gwei".to_string()],
+        value: U256::exp10(18),
+    });
+
+    assert!(parse_command("call").is_err());
+    assert!(parse_command("send f() --value").is_err());
+    assert!(parse_command("launch").unwrap_err().starts_with("unknown command"));
+}
+
+#[test]
+fn resolves_signatures_against_the_abi() {
+    let json: serde_json::Value = serde_json::json!([
+        {"type": "function", "name": "number", "inputs": [], "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"},
+    ]);
+    let abi: Abi = Abi::from_json(&json).unwrap();
+
+    let function: AbiFunction = resolve_function(&abi, "number()", &[]).unwrap();
+    assert_eq!(function.state_mutability, "view");
+    assert_eq!(function.outputs[0].ty, AbiType::Uint(256));
+
+    let function: AbiFunction = resolve_function(&abi, "pair((uint8,bool),bytes)(address,int16[2])", &[]).unwrap();
+    assert_eq!(function.signature(), "pair((uint8,bool),bytes)");
+    assert_eq!(function.outputs[1].ty.to_string(), "int16[2]");
+
+    assert!(resolve_function(&abi, "count", &[]).is_err());
+    assert!(resolve_function(&abi, "f(uint256)x", &[]).is_err());
+}
+
+#[test]
+fn resolves_bare_names_and_overloads() {
+    let json: serde_json::Value = serde_json::json!([
+        {"type": "function", "name": "number", "inputs": [], "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"},
+        {"type": "function", "name": "set", "inputs": [{"name": "flag", "type": "bool"}], "outputs": []},
+        {"type": "function", "name": "set", "inputs": [{"name": "owner", "type": "address"}], "outputs": []},
+        {"type": "function", "name": "set", "inputs": [{"name": "a", "type": "uint8"}, {"name": "b", "type": "uint8"}], "outputs": []},
+        {"type": "function", "name": "set", "inputs": [{"name": "a", "type": "uint16"}, {"name": "b", "type": "bool"}], "outputs": []},
+    ]);
+    let abi: Abi = Abi::from_json(&json).unwrap();
+    let args = |args: &[&str]| -> Vec<String> { args.iter().map(|arg: &&str| arg.to_string()).collect() };
+
+    assert_eq!(resolve_function(&abi, "number", &[]).unwrap().signature(), "number()");
+    assert_eq!(resolve_function(&abi, "set", &args(&["true"])).unwrap().signature(), "set(bool)");
+    assert_eq!(resolve_function(&abi, "set", &args(&["0x00000000000000000000000000000000000000ff"])).unwrap().signature(), "set(address)");
+    assert_eq!(resolve_function(&abi, "set", &args(&["1", "false"])).unwrap().signature(), "set(uint16,bool)");
+
+    let error: String = resolve_function(&abi, "set", &args(&["1", "1"])).unwrap_err();
+    assert!(error.contains("ambiguous") && error.contains("set(uint8,uint8)") && !error.contains("set(bool)"), "{}", error);
+    let error: String = resolve_function(&abi, "set", &args(&["maybe"])).unwrap_err();
+    assert!(error.contains("candidates: set(bool), set(address)"), "{}", error);
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+use mini_evm::{ContractAccount, TxEnv};
+use mini_evm::abi::{Abi, AbiType};
+use mini_evm::inspector::CallKind;
+use mini_evm::response::{StateChange, state_changes, transaction_json};
+use mini_evm::transaction::{TxResult, transact};
+use primitive_types::U256;
+use serde_json::{Value, json};
+mod common;
+
+#[test]
+fn diffs_storage() {
+    let before: HashMap<U256, U256> = HashMap::from([(U256::from(1), U256::from(5)), (U256::from(2), U256::from(6))]);
+    let after: HashMap<U256, U256> = HashMap::from([(U256::from(2), U256::from(6)), (U256::from(3), U256::from(7))]);
+
+    assert_eq!(state_changes(&before, &after), vec![
+        StateChange { slot: U256::from(1), before: U256::from(5), after: U256::zero() },
+        StateChange { slot: U256::from(3), before: U256::zero(), after: U256::from(7) },
+    ]);
+}
+
+#[test]
+fn describes_transactions_as_json() {
+    // SSTORE 0x2a into slot 0, LOG1 the word 7 with topic 0xaa, RETURN that word
+    let code: Vec<u8> = vec![
+        0x60, 0x2a, 0x60, 0x00, 0x55,
+        0x60, 0x07, 0x60, 0x00, 0x52,
+        0x60, 0xaa, 0x60, 0x20, 0x60, 0x00, 0xa1,
+        0x60, 0x20, 0x60, 0x00, 0xf3,
+    ];
+    let mut account: ContractAccount = common::setup(code);
+    let before: HashMap<U256, U256> = account.storage.clone();
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::zero(), vec![], &TxEnv::default(), None);
+    let changes: Vec<StateChange> = state_changes(&before, &account.storage);
+
+    let response: Value = transaction_json(&tx, &[AbiType::Uint(256)], &Abi::default(), &changes);
+    assert_eq!(response["status"], "success");
+    assert_eq!(response["outputs"], json!(["7"]));
+    assert_eq!(response["returnData"], format!("0x{:064x}", 7));
+    assert_eq!(response["gasUsed"], tx.gas_used);
+    assert_eq!(response["logs"][0]["topics"][0], format!("0x{:064x}", 0xaa));
+    assert_eq!(response["stateChanges"][0]["after"], format!("0x{:064x}", 0x2a));
+
+    // PUSH1 0, PUSH1 0, REVERT
+    let mut account: ContractAccount = common::setup(vec![0x60, 0x00, 0x60, 0x00, 0xfd]);
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::zero(), vec![], &TxEnv::default(), None);
+    let response: Value = transaction_json(&tx, &[], &Abi::default(), &[]);
+    assert_eq!(response["status"], "reverted");
+    assert_eq!(response["reason"], "execution reverted");
+    assert_eq!(response["outputs"], Value::Null);
+}
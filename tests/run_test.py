@@ -0,0 +1,25 @@
+import os
+import sys
+import unittest
+
+sys.path.insert(0, os.path.join(os.path.dirname(__file__), ".."))
+import run
+
+class PrefixedCallTest(unittest.TestCase):
+    def setUp(self):
+        self.sent = []
+        run.send = lambda cmd: self.sent.append(cmd) or {"status": "success", "outputs": []}
+        self.input_types = {"number()": [], "increment()": []}
+        self.output_types = {"number()": ["uint256"], "increment()": []}
+
+    def test_send_leaves_the_mode_to_the_abi(self):
+        # `number()` is a view function, which the binary only calls unless told otherwise
+        run.prefixed_call(self.input_types, self.output_types, "send", "number()")
+        self.assertNotIn("mode", self.sent[0])
+
+    def test_call_is_always_read_only(self):
+        run.prefixed_call(self.input_types, self.output_types, "call", "increment()")
+        self.assertEqual(self.sent[0]["mode"], "call")
+
+if __name__ == "__main__":
+    unittest.main()
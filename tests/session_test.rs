@@ -0,0 +1,186 @@
+use std::io::Write;
+use std::process::{Child, Command, Output, Stdio};
+use serde_json::{Value, json};
+
+// Stores CALLVALUE in slot 0: PUSH1 5, PUSH1 12, PUSH1 0, CODECOPY, PUSH1 5, PUSH1 0, RETURN,
+// followed by the runtime code CALLVALUE, PUSH1 0, SSTORE, STOP
+const STORE_VALUE: &str = "6005600c60003960056000f33460005500";
+
+// Runs the binary on `initcode`, answering each JSON command with a line of its own.
+fn run_session(initcode: &str, commands: &[Value]) -> Vec<Value> {
+    let lines: Vec<String> = commands.iter().map(|command: &Value| command.to_string()).collect();
+    run_lines(initcode, &lines)
+}
+
+fn run_lines(initcode: &str, lines: &[String]) -> Vec<Value> {
+    run_binary(&[initcode], lines).0
+}
+
+// The responses on stdout, and whatever was written to stderr.
+fn run_binary(args: &[&str], lines: &[String]) -> (Vec<Value>, String) {
+    let mut child: Child = Command::new(env!("CARGO_BIN_EXE_mini-evm"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let input: String = lines.iter().map(|line: &String| format!("{}\n", line)).collect();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+
+    let output: Output = child.wait_with_output().unwrap();
+    let responses: Vec<Value> = String::from_utf8(output.stdout).unwrap()
+        .lines()
+        .map(|line: &str| serde_json::from_str(line).unwrap())
+        .collect();
+    (responses, String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn calls_carry_a_value() {
+    let responses: Vec<Value> = run_session(STORE_VALUE, &[
+        json!({"type": "call", "signature": "store()", "args": [], "value": "1 gwei"}),
+        json!({"type": "get_balance"}),
+        json!({"type": "call", "signature": "store()", "args": [], "value": "1 gwei", "mode": "call"}),
+        json!({"type": "call", "signature": "store()", "args": [], "value": "lots"}),
+    ]);
+
+    assert_eq!(responses[0]["status"], "success");
+    assert_eq!(responses[0]["stateChanges"][0]["after"], format!("0x{:064x}", 1_000_000_000u64));
+    assert_eq!(responses[1]["balance"], "1000000000");
+    assert!(responses[2]["error"].as_str().unwrap().contains("cannot send value"));
+    assert!(responses[3]["error"].as_str().unwrap().starts_with("invalid number"));
+}
+
+#[test]
+fn access_lists_report_gas_as_numbers() {
+    let responses: Vec<Value> = run_session(STORE_VALUE, &[json!({"type": "access_list", "signature": "store()", "args": []})]);
+
+    assert_eq!(responses[0]["accessList"], json!([{
+        "address": "0x00000000adddecafadddecafadddecafadddecaf",
+        "storageKeys": [format!("0x{:064x}", 0)],
+    }]));
+    // listing the recipient costs 2400, and its slot costs 1900 while saving the 2100 of a cold SSTORE
+    let (with, without) = (responses[0]["gasUsed"].as_u64().unwrap(), responses[0]["gasUsedWithoutAccessList"].as_u64().unwrap());
+    assert_eq!(with, without + 2_400 - 200);
+}
+
+#[test]
+fn reading_state_leaves_missing_accounts_alone() {
+    // the address the next contract would be deployed at
+    let next: &str = "0x00000000adddecafadddecafadddecafadddecb0";
+    let responses: Vec<Value> = run_session(STORE_VALUE, &[
+        json!({"type": "get_storage", "slot": "0", "address": next}),
+        json!({"type": "get_balance", "address": next}),
+        json!({"type": "get_nonce", "address": next}),
+        json!({"type": "deploy", "bytecode": STORE_VALUE}),
+    ]);
+
+    assert_eq!(responses[0]["value"], format!("0x{:064x}", 0));
+    assert_eq!(responses[1]["balance"], "0");
+    assert_eq!(responses[2]["nonce"], 0);
+    assert_eq!(responses[3]["address"], next);
+}
+
+#[test]
+fn reverting_drops_what_came_after_the_snapshot() {
+    let responses: Vec<Value> = run_session(STORE_VALUE, &[
+        json!({"type": "snapshot"}),
+        json!({"type": "deploy", "bytecode": STORE_VALUE, "contract": "Other"}),
+        json!({"type": "snapshot"}),
+        json!({"type": "revert", "snapshot": 0}),
+        json!({"type": "use", "contract": "Other"}),
+        json!({"type": "get_nonce", "address": "0x00000000adddecafadddecafadddecafadddecb0"}),
+        json!({"type": "revert", "snapshot": 1}),
+        json!({"type": "revert", "snapshot": 0}),
+        json!({"type": "deploy", "bytecode": STORE_VALUE, "contract": "Other"}),
+    ]);
+
+    assert_eq!(responses[0]["snapshot"], 0);
+    assert_eq!(responses[2]["snapshot"], 1);
+    assert_eq!(responses[3]["status"], "success");
+    assert_eq!(responses[4]["error"], "no deployed contract named Other");
+    assert_eq!(responses[5]["nonce"], 0);
+    // both snapshots went with the revert
+    assert_eq!(responses[6]["error"], "unknown snapshot: 1");
+    assert_eq!(responses[7]["error"], "unknown snapshot: 0");
+    // the dropped contract's address is free again
+    assert_eq!(responses[8]["address"], responses[1]["address"]);
+}
+
+#[test]
+fn using_an_unknown_contract_fails() {
+    let responses: Vec<Value> = run_session(STORE_VALUE, &[
+        json!({"type": "use", "contract": "Missing"}),
+        json!({"type": "use", "contract": "0x000000000000000000000000000000000000beef"}),
+        json!({"type": "use", "contract": "Contract"}),
+    ]);
+
+    assert_eq!(responses[0]["error"], "no deployed contract named Missing");
+    assert_eq!(responses[1]["error"], "no contract deployed at 0x000000000000000000000000000000000000beef");
+    assert_eq!(responses[2]["status"], "success");
+}
+
+#[test]
+fn quitting_a_debug_session_sends_nothing() {
+    let sender: Value = json!({"type": "get_nonce", "address": "0xdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef"});
+    // debugger commands are plain lines rather than JSON
+    let lines: Vec<String> = vec![
+        sender.to_string(),
+        json!({"type": "debug", "signature": "store()", "args": []}).to_string(),
+        "step".to_string(),
+        "q".to_string(),
+        sender.to_string(),
+        json!({"type": "debug", "signature": "store()", "args": []}).to_string(),
+        "continue".to_string(),
+        "q".to_string(),
+        sender.to_string(),
+    ];
+    let responses: Vec<Value> = run_lines(STORE_VALUE, &lines);
+
+    assert_eq!(responses[0]["nonce"], 1);
+    assert_eq!(responses[3]["committed"], false);
+    assert!(responses[3].get("gasUsed").is_none());
+    assert_eq!(responses[4]["nonce"], 1);
+
+    assert_eq!(responses[7]["committed"], true);
+    assert!(responses[7]["gasUsed"].as_u64().unwrap() < 30_000_000);
+    assert_eq!(responses[8]["nonce"], 2);
+}
+
+#[test]
+fn debug_sessions_stay_out_of_the_gas_report() {
+    let lines: Vec<String> = vec![
+        json!({"type": "call", "signature": "store()", "args": []}).to_string(),
+        json!({"type": "debug", "signature": "store()", "args": []}).to_string(),
+        "q".to_string(),
+        json!({"type": "debug", "signature": "store()", "args": []}).to_string(),
+        "continue".to_string(),
+        "q".to_string(),
+    ];
+    let (_, stderr) = run_binary(&[STORE_VALUE, "--gas-report", "json"], &lines);
+
+    let report: Value = serde_json::from_str(stderr.lines().last().unwrap()).unwrap();
+    let function: &Value = &report["Contract"]["functions"]["0x975057e7"];
+    assert_eq!(function["calls"], 1);
+    assert_eq!(function["max"], function["min"]);
+}
+
+#[test]
+fn view_functions_are_called_unless_told_otherwise() {
+    let abi: std::path::PathBuf = std::env::temp_dir().join(format!("mini-evm-modes-{}.json", std::process::id()));
+    std::fs::write(&abi, r#"[
+        {"type": "function", "name": "number", "inputs": [], "outputs": [], "stateMutability": "view"},
+        {"type": "function", "name": "store", "inputs": [], "outputs": [], "stateMutability": "payable"}
+    ]"#).unwrap();
+    let lines: Vec<String> = [
+        json!({"type": "call", "signature": "number()", "args": []}),
+        json!({"type": "call", "signature": "number()", "args": [], "mode": "send"}),
+        json!({"type": "call", "signature": "store()", "args": []}),
+    ].iter().map(|command: &Value| command.to_string()).collect();
+    let (responses, _) = run_binary(&[STORE_VALUE, "--abi", abi.to_str().unwrap()], &lines);
+
+    assert_eq!(responses[0]["mode"], "call");
+    assert_eq!(responses[1]["mode"], "send");
+    assert_eq!(responses[2]["mode"], "send");
+}
@@ -0,0 +1,152 @@
+use mini_evm::{Evm, ContractAccount, TxEnv};
+use mini_evm::inspector::CallKind;
+use mini_evm::transaction::{TxResult, transact};
+use mini_evm::tracing::{CallTracer, ChromeTracer, Eip3155Tracer, PrestateTracer, Profiler};
+use primitive_types::U256;
+use serde_json::Value;
+mod common;
+
+#[test]
+fn eip3155_trace_lines() {
+    // PUSH1 2, PUSH1 3, ADD, STOP
+    let code: Vec<u8> = vec![0x60, 0x02, 0x60, 0x03, 0x01, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut out: Vec<u8> = Vec::new();
+    let mut tracer: Eip3155Tracer<&mut Vec<u8>> = Eip3155Tracer::new(&mut out);
+
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+    evm.gas_limit = 100;
+    evm.inspector = Some(&mut tracer);
+    evm.run().unwrap();
+
+    let lines: Vec<Value> = String::from_utf8(out).unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[2]["op"], 0x01);
+    assert_eq!(lines[2]["opName"], "ADD");
+    assert_eq!(lines[2]["gas"], "0x5e");
+    assert_eq!(lines[2]["gasCost"], "0x3");
+    assert_eq!(lines[2]["stack"], serde_json::json!(["0x2", "0x3"]));
+    assert_eq!(lines[4]["gasUsed"], "0x9");
+    assert_eq!(lines[4]["pass"], true);
+}
+
+// SLOAD slot 0, add one, SSTORE it back, LOG1 0x42, STOP
+fn counter_code() -> Vec<u8> {
+    vec![0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x60, 0x42, 0x60, 0x00, 0x60, 0x00, 0xa1, 0x00]
+}
+
+#[test]
+fn call_tracer_frame() {
+    let mut account: ContractAccount = common::setup(counter_code());
+    let mut tracer: CallTracer = CallTracer::new();
+
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![0xde, 0xad]);
+    evm.inspector = Some(&mut tracer);
+    evm.run().unwrap();
+
+    let trace: Value = serde_json::to_value(tracer.result().unwrap()).unwrap();
+    assert_eq!(trace["type"], "CALL");
+    assert_eq!(trace["from"], "0xdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef");
+    assert_eq!(trace["input"], "0xdead");
+    assert_eq!(trace["logs"][0]["topics"][0], "0x0000000000000000000000000000000000000000000000000000000000000042");
+    assert!(trace.get("error").is_none());
+}
+
+#[test]
+fn call_tracer_reports_transaction_gas_at_the_top() {
+    let mut account: ContractAccount = common::setup(counter_code());
+    let mut tracer: CallTracer = CallTracer::new();
+    let env: TxEnv = TxEnv::default();
+
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::zero(), vec![0xde, 0xad], &env, Some(&mut tracer));
+    assert_eq!(serde_json::to_value(tracer.result().unwrap()).unwrap()["gasUsed"], format!("{:#x}", tx.execution_gas));
+
+    tracer.finish_transaction(env.gas_limit, tx.gas_used);
+    let trace: Value = serde_json::to_value(tracer.result().unwrap()).unwrap();
+    assert_eq!(trace["gas"], format!("{:#x}", env.gas_limit));
+    assert_eq!(trace["gasUsed"], format!("{:#x}", tx.gas_used));
+    assert!(tx.gas_used > tx.execution_gas);
+}
+
+#[test]
+fn prestate_tracer_diff() {
+    let mut account: ContractAccount = common::setup(counter_code());
+    account.storage.insert(U256::zero(), U256::from(7));
+    let mut tracer: PrestateTracer = PrestateTracer::new(&account);
+
+    let mut evm: Evm = Evm::new(&mut account, U256::zero(), vec![]);
+    evm.inspector = Some(&mut tracer);
+    evm.run().unwrap();
+
+    let diff: Value = serde_json::to_value(tracer.diff()).unwrap();
+    let slot: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+    let address: &str = "0x00000000adddecafadddecafadddecafadddecaf";
+    assert_eq!(diff["pre"][address]["storage"][slot], "0x0000000000000000000000000000000000000000000000000000000000000007");
+    assert_eq!(diff["post"][address]["storage"][slot], "0x0000000000000000000000000000000000000000000000000000000000000008");
+}
+
+#[test]
+fn prestate_tracer_diff_of_a_value_transfer() {
+    let mut account: ContractAccount = common::setup(vec![0x00]);
+    let sender: ContractAccount = ContractAccount { address: mini_evm::MSG_SENDER, balance: U256::from(100), ..common::setup(vec![]) };
+    let mut tracer: PrestateTracer = PrestateTracer::new(&account).with_caller(&sender);
+
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::from(30), vec![], &TxEnv::default(), Some(&mut tracer));
+    assert!(tx.success());
+
+    let diff: Value = serde_json::to_value(tracer.diff()).unwrap();
+    let address: &str = "0x00000000adddecafadddecafadddecafadddecaf";
+    let caller: &str = "0xdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef";
+    assert_eq!(diff["pre"][address]["balance"], "0x0");
+    assert_eq!(diff["post"][address]["balance"], "0x1e");
+    assert_eq!(diff["pre"][caller]["balance"], "0x64");
+    assert_eq!(diff["post"][caller]["balance"], "0x46");
+}
+
+#[test]
+fn profiles_gas_per_opcode() {
+    // PUSH1 1, PUSH1 0, SSTORE, PUSH1 2, PUSH1 1, SSTORE, STOP
+    let code: Vec<u8> = vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x02, 0x60, 0x01, 0x55, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut profiler: Profiler = Profiler::new();
+
+    let mut evm: Evm<'_> = Evm::new(&mut account, U256::zero(), vec![]);
+    evm.inspector = Some(&mut profiler);
+    evm.run().unwrap();
+
+    assert_eq!(profiler.opcodes[&0x60].count, 4);
+    assert_eq!(profiler.opcodes[&0x60].gas, 12);
+    assert_eq!(profiler.opcodes[&0x55].gas, 2 * 22_100);
+
+    let hottest: &mini_evm::tracing::profiler::Hotspot = &profiler.hotspots()[0];
+    assert_eq!((hottest.pc, hottest.opcode), (0x04, 0x55));
+    assert!(profiler.folded().contains(";SSTORE 44200\n"));
+}
+
+#[test]
+fn chrome_trace_events() {
+    // PUSH1 1, PUSH1 0, SSTORE, PUSH1 0, PUSH1 0, LOG0, STOP
+    let code: Vec<u8> = vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0xa0, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+    let mut tracer: ChromeTracer = ChromeTracer::new();
+    tracer.contract_names.insert(mini_evm::CONTRACT_ADDRESS, "Counter".to_string());
+    tracer.function_names.insert([0xd0, 0x9d, 0xe0, 0x8a], "increment()".to_string());
+
+    let mut evm: Evm<'_> = Evm::new(&mut account, U256::zero(), vec![0xd0, 0x9d, 0xe0, 0x8a]);
+    evm.inspector = Some(&mut tracer);
+    evm.run().unwrap();
+
+    let trace: Value = tracer.to_json();
+    let events: Vec<(&str, &str)> = trace["traceEvents"].as_array().unwrap().iter()
+        .filter(|event: &&Value| event["ph"] != "C")
+        .map(|event: &Value| (event["ph"].as_str().unwrap(), event["name"].as_str().unwrap()))
+        .collect();
+    assert_eq!(events, vec![("B", "Counter::increment()"), ("i", "SSTORE"), ("i", "LOG0"), ("E", "Counter::increment()")]);
+
+    let last: &Value = trace["traceEvents"].as_array().unwrap().iter().rev().find(|event: &&Value| event["ph"] == "C").unwrap();
+    assert_eq!(last["args"]["used"], 3 + 3 + 22_100 + 3 + 3 + 375);
+}
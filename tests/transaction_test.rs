@@ -0,0 +1,65 @@
+use mini_evm::{ContractAccount, ExitReason, TxEnv};
+use mini_evm::abi;
+use mini_evm::inspector::CallKind;
+use mini_evm::transaction::{TxResult, transact};
+use primitive_types::U256;
+mod common;
+
+#[test]
+fn deploys_with_constructor_arguments_and_value() {
+    // copies the word after the initcode (the constructor argument) into slot 0, the callvalue into slot 1:
+    // PUSH1 0x20, PUSH1 0x12, PUSH1 0, CODECOPY, PUSH1 0, MLOAD, PUSH1 0, SSTORE,
+    // CALLVALUE, PUSH1 1, SSTORE, STOP
+    let initcode: Vec<u8> = vec![0x60, 0x20, 0x60, 0x12, 0x60, 0x00, 0x39, 0x60, 0x00, 0x51, 0x60, 0x00, 0x55, 0x34, 0x60, 0x01, 0x55, 0x00];
+    let constructor_args: Vec<u8> = abi::encode_args(&["uint256".to_string()], &["42".to_string()]).unwrap();
+    let mut account: ContractAccount = common::setup([initcode, constructor_args].concat());
+
+    let tx: TxResult = transact(&mut account, CallKind::Create, U256::from(1_000), vec![], &TxEnv::default(), None);
+    assert!(matches!(tx.result, Ok(ExitReason::Stop)));
+    assert_eq!(account.storage[&U256::zero()], U256::from(42));
+    assert_eq!(account.storage[&U256::one()], U256::from(1_000));
+    assert_eq!(account.balance, U256::from(1_000));
+
+    assert!(abi::encode_args(&["uint256".to_string()], &[]).is_err());
+}
+
+#[test]
+fn failed_transaction_leaves_no_changes() {
+    // PUSH1 7, PUSH1 0, SSTORE, PUSH1 0, PUSH1 0, REVERT
+    let code: Vec<u8> = vec![0x60, 0x07, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0xfd];
+    let mut account: ContractAccount = common::setup(code);
+    account.storage.insert(U256::zero(), U256::from(3));
+
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::from(5), vec![], &TxEnv::default(), None);
+    assert!(!tx.success());
+    assert_eq!(account.storage[&U256::zero()], U256::from(3));
+    assert_eq!(account.balance, U256::zero());
+}
+
+#[test]
+fn runs_at_the_account_address() {
+    // ADDRESS, PUSH1 0, SSTORE, STOP
+    let code: Vec<u8> = vec![0x30, 0x60, 0x00, 0x55, 0x00];
+    let mut account: ContractAccount = ContractAccount { address: U256::from(0xbeef), ..common::setup(code) };
+
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::zero(), vec![], &TxEnv::default(), None);
+    assert!(tx.success());
+    assert_eq!(account.storage[&U256::zero()], U256::from(0xbeef));
+}
+
+#[test]
+fn static_calls_cannot_change_state() {
+    // NUMBER, PUSH1 0, SSTORE, STOP
+    let code: Vec<u8> = vec![0x43, 0x60, 0x00, 0x55, 0x00];
+    let mut account: ContractAccount = common::setup(code);
+
+    let env: TxEnv = TxEnv { is_static: true, ..TxEnv::default() };
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::zero(), vec![], &env, None);
+    assert_eq!(tx.result.unwrap_err(), "State change in static context");
+    assert!(account.storage.is_empty());
+
+    let env: TxEnv = TxEnv { block_number: 42, ..TxEnv::default() };
+    let tx: TxResult = transact(&mut account, CallKind::Call, U256::zero(), vec![], &env, None);
+    assert!(tx.success());
+    assert_eq!(account.storage[&U256::zero()], U256::from(42));
+}